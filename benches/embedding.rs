@@ -7,15 +7,7 @@ use sgtk::graph::Graph16;
 use sgtk::embedding::RotationSystem16;
 
 pub fn benchmark(c: &mut Criterion) {
-    let mut k33 = Graph16::empty();
-    for i in 0..6 {
-        k33.add_node(i);
-    }
-    for i in 0..3 {
-        for j in 3..6 {
-            k33.add_edge(i, j);
-        }
-    }
+    let k33: Graph16 = sgtk::families::complete_bipartite(3, 3).unwrap();
 
     c.bench_function("count_k5_faces", |b| b.iter(|| {
         RotationSystem16::simple(&black_box(Graph16::complete(5)))
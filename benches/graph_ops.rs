@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use sgtk::bitset;
+use sgtk::graph::{BitsetGraph, BitsetRows};
+use sgtk::graph::rows::scalar;
+use sgtk::permutation::Permutation;
+
+fn reversed<P: Permutation>(n: usize) -> P {
+    P::from_iter((0..n).map(|i| (i, n - 1 - i))).unwrap()
+}
+
+/// Compares the row operations used by `BitsetGraph` against the portable
+/// loops, on an AVX2 cpu the former are the SIMD kernels
+fn bench_rows<B: BitsetRows, const N: usize>(c: &mut Criterion, name: &str, n: usize) {
+    let a = sgtk::random::graph::<BitsetGraph<B, N>>(n).to_raw();
+    let b = sgtk::random::graph::<BitsetGraph<B, N>>(n).to_raw();
+    let selected = B::mask_le(n / 2);
+    let perm = reversed::<B::Perm>(N);
+
+    let mut group = c.benchmark_group(format!("{}_subgraph", name));
+    group.bench_function("scalar", |bench| bench.iter(|| scalar::subgraph(black_box(&a), &selected)));
+    group.bench_function("simd", |bench| bench.iter(|| B::subgraph_rows(black_box(&a), &selected)));
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{}_is_supergraph", name));
+    group.bench_function("scalar", |bench| bench.iter(|| scalar::is_supergraph(black_box(&a), black_box(&a))));
+    group.bench_function("simd", |bench| bench.iter(|| B::is_supergraph_rows(black_box(&a), black_box(&a))));
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{}_union", name));
+    group.bench_function("scalar", |bench| bench.iter(|| scalar::union(black_box(&a), black_box(&b))));
+    group.bench_function("simd", |bench| bench.iter(|| B::union_rows(black_box(&a), black_box(&b))));
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{}_difference", name));
+    group.bench_function("scalar", |bench| bench.iter(|| scalar::difference(black_box(&a), black_box(&b))));
+    group.bench_function("simd", |bench| bench.iter(|| B::difference_rows(black_box(&a), black_box(&b))));
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{}_shuffle", name));
+    group.bench_function("scalar", |bench| bench.iter(|| scalar::shuffle(black_box(&a), &perm)));
+    group.bench_function("simd", |bench| bench.iter(|| B::shuffle_rows(black_box(&a), &perm)));
+    group.finish();
+}
+
+pub fn benchmark(c: &mut Criterion) {
+    bench_rows::<bitset::Bitset32, 32>(c, "graph32", 32);
+    bench_rows::<bitset::Bitset64, 64>(c, "graph64", 64);
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -4,7 +4,8 @@ extern crate criterion;
 use criterion::{black_box, Criterion};
 
 pub fn benchmark(c: &mut Criterion) {
-    let graph: sgtk::graph::Graph16 = sgtk::parse::from_graph6("F@|ZO");
+    let graph: sgtk::graph::Graph16 = sgtk::parse::from_graph6("F@|ZO")
+        .unwrap();
     c.bench_function("planar_test", |b| b.iter(|| {
         sgtk::planar::fastdmp(&black_box(graph))
     }));
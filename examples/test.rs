@@ -21,7 +21,7 @@ fn main() {
     }
 
 
-    let graph: Graph16 = sgtk::parse::from_graph6("L?Xq`A_[CEOWBg");
+    let graph: Graph16 = sgtk::parse::from_graph6("L?Xq`A_[CEOWBg").unwrap();
 
     for minor in subgraphs(&graph).filter(|g| g.is_connected()) {
         let embedding = toroidal::find_embedding(&minor);
@@ -55,7 +55,7 @@ fn main() {
         dbg!(sgtk::toroidal::find_embedding(&minor));
     }
 
-    viz::render_dot("test.pdf", &[k33, canon]);
+    viz::render_svg("test.svg", &[k33, canon]).unwrap();
 
     return;
 
@@ -74,7 +74,7 @@ fn main() {
     }
     */
 
-    dbg!(parse::from_graph6::<Graph16>("CF"));
+    dbg!(parse::from_graph6::<Graph16>("CF").unwrap());
 
     /*
     let mut graph = random::graph16(8); //Graph16::regular(8);
@@ -98,7 +98,7 @@ fn main() {
     */
     //let graph = parse::from_upper_tri("9 111000011100001100001000011111111111").unwrap();
         //Graph16::regular(8);
-    let graph = parse::from_graph6::<Graph16>("H_?xqKw");
+    let graph = parse::from_graph6::<Graph16>("H_?xqKw").unwrap();
 
     dbg!(graph);
 
@@ -111,7 +111,7 @@ fn main() {
         graphs.push(minor);
     }
 
-    viz::render_dot("test.pdf", &graphs);
+    viz::render_svg("test.svg", &graphs).unwrap();
     let embedding = toroidal::find_embedding(&graph);
 
     dbg!(&embedding);
@@ -142,7 +142,7 @@ fn main() {
             for bridge in toroidal::compute_bridges(&minor, &k) {
                 graphs.push(bridge);
             }
-            viz::render_dot("test.pdf", &graphs);
+            viz::render_svg("test.svg", &graphs).unwrap();
         }
         assert!(embedding.is_some());
     }
@@ -302,7 +302,7 @@ fn main() {
     let graph = Graph16::regular(16);
     graphs.push((graph, None)); 
 
-    viz::render_dot("test.pdf", &graphs);
+    viz::render_svg("test.svg", &graphs).unwrap();
 
     graphs.push((graph.to_canonical(), None));
     */
@@ -381,7 +381,7 @@ fn main() {
     }
     */
 
-    viz::render_dot("test.pdf", &graphs);
+    viz::render_svg("test.svg", &graphs).unwrap();
 
 
     /*
@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use std::io::Write;
 use std::time::{Instant, Duration};
 use structopt::StructOpt;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use console::style;
 use f4m::{Stats, TimingStats};
@@ -15,7 +15,7 @@ use f4m::{Stats, TimingStats};
 struct Opt {
     #[structopt(long)]
     print_edge_count: bool,
-    /// Known torus obstrutions in upper triangle format
+    /// Known torus obstrutions
     #[structopt(short, long, parse(from_os_str))]
     known_obstructions: Option<PathBuf>,
     /// List of new torus obstructions
@@ -36,9 +36,6 @@ struct Opt {
     /// Do timing tests
     #[structopt(long)]
     time: bool,
-    /// Input graph is in upper triangle format
-    #[structopt(long)]
-    triangle: bool,
 }
 
 fn is_obstruction(graph: &Graph32) -> bool {
@@ -76,22 +73,17 @@ fn is_minor_obstruction(graph: &Graph32) -> bool {
 
 fn main() -> Result<()> {
     let opt = Opt::from_args();
-    let progress_style = ProgressStyle::default_bar()
-        .template("{prefix}{bar:40.cyan/blue} {pos:>7}/{len:7} {elapsed} elapsed, est {eta} left {msg}")
-        .progress_chars("#> ");
+    let spinner_style = ProgressStyle::default_spinner()
+        .template("{prefix}{spinner} {pos:>7} graphs, {elapsed} elapsed {msg}");
 
     let mut known_obstructions = HashSet::new();
     if let Some(known) = opt.known_obstructions {
         eprintln!("Loading known obstructions");
-        let file = std::fs::read_to_string(&known)
-            .with_context(|| format!("Failed to read known obstrucions from {:?}", &known))?;
-        let num = file.lines().count();
-
-        let bar = ProgressBar::new(num as u64);
-        bar.set_style(progress_style.clone());
-        for line in file.lines() {
-            let graph = sgtk::parse::from_upper_tri::<Graph32>(line)
-                .ok_or(anyhow!("Known obstruction contains more than 32 vertices"))?;
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(spinner_style.clone());
+        for graph in f4m::read_graphs::<Graph32>(&known)? {
+            let graph = graph
+                .with_context(|| format!("Failed to read known obstrucions from {:?}", &known))?;
             known_obstructions.insert(graph.to_canonical());
             bar.inc(1);
         }
@@ -111,19 +103,12 @@ fn main() -> Result<()> {
 
     let num_len = opt.new_obstructions.len().to_string().len();
     for (i, obstr_file) in opt.new_obstructions.iter().enumerate() {
-        let file = std::fs::read_to_string(obstr_file)
-            .with_context(|| format!("Failed to read found obstrucions from {:?}", &obstr_file))?;
-        let num = file.lines().count();
-        let bar = ProgressBar::new(num as u64);
-        bar.set_style(progress_style.clone());
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(spinner_style.clone());
         bar.set_prefix(&format!("[{:width$}/{:width$}] ", i+1, opt.new_obstructions.len(), width = num_len));
-        for line in file.lines() {
-            let mut graph = if opt.triangle {
-                sgtk::parse::from_upper_tri::<Graph32>(line)
-                    .ok_or(anyhow!("Known obstruction contains more than 32 vertices"))?
-            } else {
-                sgtk::parse::from_graph6::<Graph32>(line)
-            };
+        for graph in f4m::read_graphs::<Graph32>(obstr_file)? {
+            let mut graph = graph
+                .with_context(|| format!("Failed to read found obstrucions from {:?}", &obstr_file))?;
             if opt.to_canonical {
                 graph = graph.to_canonical();
             }
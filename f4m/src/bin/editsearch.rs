@@ -4,7 +4,7 @@ use std::collections::{HashSet, HashMap};
 use std::path::PathBuf;
 use std::io::Write;
 use structopt::StructOpt;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use console::style;
 use f4m::Stats;
@@ -21,12 +21,6 @@ struct Opt {
     /// Other known obstructions
     #[structopt(short, long, parse(from_os_str))]
     known: Option<PathBuf>,
-    /// If other known obstructions are in upper triangle format
-    #[structopt(long)]
-    known_triangle: bool,
-    /// If graphs are in upper triangle format
-    #[structopt(long)]
-    triangle: bool,
     /// Obstrutions for starting point
     #[structopt(parse(from_os_str))]
     obstructions: PathBuf,
@@ -38,24 +32,20 @@ fn main() -> Result<()> {
     let progress_style = ProgressStyle::default_bar()
         .template("{prefix}{bar:40.cyan/blue} {pos:>7}/{len:7} {elapsed} elapsed, est {eta} left {msg}")
         .progress_chars("#> ");
+    let spinner_style = ProgressStyle::default_spinner()
+        .template("{prefix}{spinner} {pos:>7} graphs, {elapsed} elapsed {msg}");
 
     let mut known_obstructions = HashSet::new();
     let mut search_obstructions = HashSet::new();
     
     if let Some(known) = opt.known.as_ref() {
         eprintln!("Loading other known obstructions");
-        let file = std::fs::read_to_string(known)
-            .with_context(|| format!("Failed to read obstructions from {:?}", known))?;
-        let num = file.lines().count();
-        let bar = ProgressBar::new(num as u64);
-        bar.set_style(progress_style.clone());
-        for (i, line) in file.lines().enumerate() {
-            let mut graph = if opt.known_triangle {
-                sgtk::parse::from_upper_tri::<Graph32>(line)
-                    .ok_or(anyhow!("Known obstruction contains more than 32 vertices"))?
-            } else {
-                sgtk::parse::from_graph6::<Graph32>(line)
-            }.to_canonical();
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(spinner_style.clone());
+        for graph in f4m::read_graphs::<Graph32>(known)? {
+            let graph = graph
+                .with_context(|| format!("Failed to read obstructions from {:?}", known))?
+                .to_canonical();
             known_obstructions.insert(graph);
             bar.inc(1);
         }
@@ -63,9 +53,6 @@ fn main() -> Result<()> {
     }
 
     eprintln!("Loading obstructions");
-    let file = std::fs::read_to_string(&opt.obstructions)
-        .with_context(|| format!("Failed to read obstructions from {:?}", &opt.obstructions))?;
-    let num = file.lines().count();
 
     let (mr_mod, mr_res) = if let Some((a, b)) = opt.modres.as_ref()
         .and_then(|s| s.split_once('/'))
@@ -79,15 +66,12 @@ fn main() -> Result<()> {
         (1, 0)
     };
 
-    let bar = ProgressBar::new(num as u64);
-    bar.set_style(progress_style.clone());
-    for (i, line) in file.lines().enumerate() {
-        let mut graph = if opt.triangle {
-            sgtk::parse::from_upper_tri::<Graph32>(line)
-                .ok_or(anyhow!("Known obstruction contains more than 32 vertices"))?
-        } else {
-            sgtk::parse::from_graph6::<Graph32>(line)
-        }.to_canonical();
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(spinner_style.clone());
+    for (i, graph) in f4m::read_graphs::<Graph32>(&opt.obstructions)?.enumerate() {
+        let graph = graph
+            .with_context(|| format!("Failed to read obstructions from {:?}", &opt.obstructions))?
+            .to_canonical();
         known_obstructions.insert(graph);
         if i % mr_mod == mr_res {
             search_obstructions.insert(graph);
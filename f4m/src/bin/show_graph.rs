@@ -3,18 +3,15 @@ use sgtk::prelude::*;
 use std::path::PathBuf;
 use std::io::Write;
 use structopt::StructOpt;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 
 #[derive(StructOpt, Debug)]
-#[structopt(name = "show-graphs", about = "Tool to render graphs in graph6 format.")]
+#[structopt(name = "show-graphs", about = "Tool to render graphs in graph6, sparse6 or upper triangle format.")]
 struct Opt {
     /// Tikz format
     #[structopt(long)]
     tikz: bool,
-    /// Input is in upper triangle format
-    #[structopt(long)]
-    triangle: bool,
-    /// Output file
+    /// Output file, svg files are rendered without graphviz
     #[structopt(short, long)]
     output: String,
     /// Input file
@@ -25,19 +22,9 @@ struct Opt {
 fn main() -> Result<()> {
     let opt = Opt::from_args();
 
-    let file = std::fs::read_to_string(&opt.input)
-        .with_context(|| format!("Failed to read known obstrucions from {:?}", &opt.input))?;
-
-    let graphs: Vec<Graph64> = if opt.triangle {
-        file.lines()
-            .map(|l| sgtk::parse::from_upper_tri(l)
-                .ok_or(anyhow!("Could not parse graph")))
-            .collect::<Result<_>>()?
-    } else {
-        file.lines()
-            .map(|l| sgtk::parse::from_graph6(l))
-            .collect()
-    };
+    let graphs: Vec<Graph64> = f4m::read_graphs(&opt.input)?
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Could not read graphs from {:?}", &opt.input))?;
 
     if opt.tikz {
         let mut output = std::fs::File::create(opt.output)?;
@@ -46,8 +33,12 @@ fn main() -> Result<()> {
             write!(output, "{}\n", tikz)
                 .with_context(|| "Cannot write tikz graph to file")?;
         }
+    } else if opt.output.ends_with(".svg") {
+        sgtk::viz::render_svg(&opt.output, &graphs)
+            .with_context(|| "Cannot write svg file")?;
     } else {
-        sgtk::viz::render_dot(&opt.output, &graphs);
+        sgtk::viz::render_dot(&opt.output, &graphs)
+            .with_context(|| "Cannot render graphs with fdp")?;
     }
     Ok(())
 }
@@ -164,12 +164,17 @@ fn main() -> Result<()> {
     for path in &opt.splitdel_graphs {
         let file = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read split-delete graph from {:?}", path))?;
-        for line in file.lines() {
+        for (i, line) in file.lines().enumerate() {
             let mut words = line.split(' ');
             let u = words.next()
                 .ok_or(anyhow!("Split-delete graph file contains empty line"))?;
-            let u: Graph32 = sgtk::parse::from_graph6(&u);
-            let vs = words.map(|w| sgtk::parse::from_graph6(&w))
+            let u: Graph32 = sgtk::parse::from_graph6(&u)
+                .map_err(|e| e.with_line(i+1))
+                .with_context(|| format!("Failed to parse split-delete graph in {:?}", path))?;
+            let vs = words.map(|w| sgtk::parse::from_graph6(&w).map_err(|e| e.with_line(i+1)))
+                .collect::<Result<Vec<Graph32>, _>>()
+                .with_context(|| format!("Failed to parse split-delete graph in {:?}", path))?
+                .into_iter()
                 .filter(|v| v != &u)
                 .collect::<Vec<Graph32>>();
             
@@ -276,7 +281,8 @@ fn main() -> Result<()> {
     obstructions.print("Obstructions");
 
     if let Some(start) = opt.start_graph {
-        let graph = sgtk::parse::from_graph6(&start);
+        let graph = sgtk::parse::from_graph6(&start)
+            .context("Failed to parse start graph")?;
         let mut reachable = Stats::new();
         for g in dfs(&splitdel_graph, graph) {
             reachable.add_graph(g);
@@ -1,4 +1,4 @@
-use sgtk::graph::{subgraphs, Graph16, Graph32, Graph64};
+use sgtk::graph::{subgraphs, Graph16, Graph32, Graph64, Graph128};
 use sgtk::prelude::*;
 use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
@@ -11,7 +11,7 @@ struct Opt {
     /// Number of random graphs to test
     #[structopt(short, long, default_value="100")]
     count: u64,
-    /// Size of random graphs
+    /// Size of random graphs, at most 128
     #[structopt(short, default_value="63")]
     n: usize,
     /// Output file
@@ -19,7 +19,7 @@ struct Opt {
     output: Option<PathBuf>,
 }
 
-fn find_toroidal_obstruction<G: Graph + Ord>(mut graph: G, subgraph: Option<G>) -> Graph64 {
+fn find_toroidal_obstruction<G: Graph + Ord>(mut graph: G, subgraph: Option<G>) -> Graph128 {
     if G::MAXN > 16 {
         let node_count = graph.nodes().count();
         if node_count < 16 {
@@ -28,6 +28,9 @@ fn find_toroidal_obstruction<G: Graph + Ord>(mut graph: G, subgraph: Option<G>)
         } else if G::MAXN > 32 && node_count < 32 {
             graph.trim();
             return find_toroidal_obstruction::<Graph32>(graph.convert(), None)
+        } else if G::MAXN > 64 && node_count < 64 {
+            graph.trim();
+            return find_toroidal_obstruction::<Graph64>(graph.convert(), None)
         }
     }
 
@@ -61,11 +64,32 @@ struct Stats {
     num_disconnected: u64,
     num_obstructions: u64,
     count_sizes: BTreeMap<usize, u64>,
-    obstructions: HashMap<Graph64, u64>,
+    obstructions: HashMap<Graph128, u64>,
+}
+
+enum Outcome {
+    Disconnected,
+    Toroidal,
+    Obstruction(Graph128),
+}
+
+fn test_random_graph<G: Graph + Ord>(n: usize) -> Outcome {
+    let graph: G = sgtk::random::graph(n);
+    if !graph.is_connected() {
+        Outcome::Disconnected
+    } else if sgtk::toroidal::find_embedding(&graph).is_none() {
+        Outcome::Obstruction(find_toroidal_obstruction(graph, None))
+    } else {
+        Outcome::Toroidal
+    }
 }
 
 fn main() {
     let opt = Opt::from_args();
+    if opt.n > Graph128::MAXN {
+        eprintln!("Random graphs can have at most {} nodes", Graph128::MAXN);
+        std::process::exit(1);
+    }
 
     let mut output = opt.output.map(|path| std::fs::File::create(path).unwrap())
         .unwrap_or_else(|| std::fs::File::create("/dev/stdout").unwrap());
@@ -79,20 +103,21 @@ fn main() {
     };
 
     for _ in 0..opt.count {
-        let graph: Graph64 = sgtk::random::graph(opt.n);
-        if !graph.is_connected() {
-            stats.num_disconnected += 1;
-            continue
-        }
-
-        if sgtk::toroidal::find_embedding(&graph).is_none() {
-            stats.num_obstructions += 1;
-            let obstruction = find_toroidal_obstruction(graph, None);
-            *stats.count_sizes.entry(obstruction.nodes().count()).or_insert(0) += 1;
-            *stats.obstructions.entry(obstruction).or_insert(0) += 1;
-            write!(output, "{}\n", sgtk::parse::to_graph6(&obstruction)).unwrap();
+        let outcome = if opt.n > Graph64::MAXN {
+            test_random_graph::<Graph128>(opt.n)
         } else {
-            stats.num_toroidal += 1;
+            test_random_graph::<Graph64>(opt.n)
+        };
+
+        match outcome {
+            Outcome::Disconnected => stats.num_disconnected += 1,
+            Outcome::Toroidal => stats.num_toroidal += 1,
+            Outcome::Obstruction(obstruction) => {
+                stats.num_obstructions += 1;
+                *stats.count_sizes.entry(obstruction.nodes().count()).or_insert(0) += 1;
+                *stats.obstructions.entry(obstruction).or_insert(0) += 1;
+                write!(output, "{}\n", sgtk::parse::to_graph6(&obstruction)).unwrap();
+            }
         }
     }
     output.flush().unwrap();
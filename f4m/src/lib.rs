@@ -2,11 +2,23 @@ use sgtk::graph::{minors, subgraphs, Graph32};
 use sgtk::prelude::*;
 use console::style;
 use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
 use std::time::{Duration, Instant};
+use sgtk::parse::GraphReader;
+use anyhow::{Context, Result};
 
 pub mod splitdel;
 pub mod editsearch;
 
+/// Opens a file of graphs in any of the formats `GraphReader` detects
+pub fn read_graphs<G: Graph>(path: &Path) -> Result<GraphReader<BufReader<File>, G>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    Ok(GraphReader::new(BufReader::new(file)))
+}
+
 pub fn is_obstruction<G: Graph>(graph: &G) -> bool {
     for u in graph.nodes().iter() {
         if graph.siblings(u).count() < 3 {
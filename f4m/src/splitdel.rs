@@ -1,7 +1,7 @@
 use sgtk::graph::{subgraphs, Graph32};
-use sgtk::bitset::Bitset32;
 use sgtk::prelude::*;
 use std::collections::{HashSet, HashMap};
+use std::hash::Hash;
 use crate::is_obstruction;
 
 fn search_obstruction<G: Graph>(mut graph: G, u: usize, mut edges: G::Set) -> Option<G> {
@@ -142,7 +142,7 @@ pub fn find_all_obstructions<G: Graph>(graph: G) -> Vec<G> {
 */
 
 
-pub fn find_splitdel_min(graph: &sgtk::graph::Graph32) -> Option<sgtk::graph::Graph32> {
+pub fn find_splitdel_min<G: Graph>(graph: &G) -> Option<G> {
     for (u, v) in graph.edges() {
         let mut graph = graph.clone();
         graph.contract_edge(u, v);
@@ -169,7 +169,7 @@ pub fn find_splitdel_min(graph: &sgtk::graph::Graph32) -> Option<sgtk::graph::Gr
     None
 }
 
-pub fn gen_splitdel<'a>(graph: &'a Graph32) -> impl 'a + Iterator<Item = Graph32> {
+pub fn gen_splitdel<G: Graph + Ord + Hash>(graph: &G) -> impl Iterator<Item = G> {
     let v = graph.nodes().invert().smallest().unwrap();
     /*
     graph.nodes().iter().flat_map(move |u| {
@@ -286,13 +286,13 @@ impl<G: Graph + Ord> SplitDelSearcher<G> {
     }
 }
 
-struct SplitDelSearcher2 {
-    found: Vec<Graph32>,
-    visited: HashSet<Graph32>,
-    mem: HashMap<Graph32, bool>,
+struct SplitDelSearcher2<G> {
+    found: Vec<G>,
+    visited: HashSet<G>,
+    mem: HashMap<G, bool>,
 }
 
-impl SplitDelSearcher2 {
+impl<G: Graph + Ord + Hash> SplitDelSearcher2<G> {
     fn new() -> Self {
         Self {
             found: Vec::new(),
@@ -301,7 +301,7 @@ impl SplitDelSearcher2 {
         }
     }
 
-    fn split_search(&mut self, mut graph: Graph32, u: usize, v: usize, mut es: Bitset32) {
+    fn split_search(&mut self, mut graph: G, u: usize, v: usize, mut es: G::Set) {
         if let Some(w) = es.smallest() {
             es.clear(w);
             self.split_search(graph.clone(), u, v, es.clone());
@@ -313,7 +313,7 @@ impl SplitDelSearcher2 {
         }
     }
 
-    fn is_toroidal(&mut self, graph: &Graph32) -> bool {
+    fn is_toroidal(&mut self, graph: &G) -> bool {
         if let Some(r) = self.mem.get(graph) {
             *r
         } else {
@@ -323,7 +323,7 @@ impl SplitDelSearcher2 {
         }
     }
 
-    fn del_search(&mut self, graph: Graph32) {
+    fn del_search(&mut self, graph: G) {
         let graph = graph.to_canonical();
         if self.visited.contains(&graph) {
             return
@@ -349,7 +349,7 @@ impl SplitDelSearcher2 {
 
         if obstruction {
             let mut graph = graph;
-            for u in graph.nodes() {
+            for u in graph.nodes().iter() {
                 /*
                 if graph.siblings(u).count() < 2 {
                     graph.del_node(u);
@@ -368,68 +368,6 @@ impl SplitDelSearcher2 {
     }
 }
 
-fn compute_orbits(graph: Graph32) -> [usize; 32] {
-    let n = graph.nodes().count();
-    let auto_gens = sgtk::iso::search_tree(graph).automorphisms;
-
-    let mut orbits = [0; 32];
-    for i in 0..n {
-        orbits[i] = i;
-    }
-
-    for perm in auto_gens {
-        for i in 0..n {
-            if perm.get(i) != i {
-                let mut j1 = usize::from(orbits[i]);
-                while usize::from(orbits[j1]) != j1 {
-                    j1 = usize::from(orbits[j1]);
-                }
-
-                let mut j2 = usize::from(orbits[perm.get(i)]);
-                while usize::from(orbits[j2]) != j2 {
-                    j2 = usize::from(orbits[j2]);
-                }
-
-                if j1 < j2 {
-                    orbits[j2] = j1;
-                } else {
-                    orbits[j1] = j2;
-                }
-            }
-        }
-
-        for i in 0..n {
-            orbits[i] = orbits[orbits[i]];
-        }
-    }
-
-    //dbg!(&orbits);
-
-    orbits
-}
-
-fn compute_autos(graph: Graph32) -> HashSet<Graph32> {
-    let auto_gens = sgtk::iso::search_tree(graph).automorphisms;
-
-    let mut autos = HashSet::new();
-
-    let mut queue = Vec::new();
-    queue.push(graph);
-
-    while let Some(graph) = queue.pop() {
-        for perm in &auto_gens {
-            let mut g = graph.clone();
-            g.shuffle(perm);
-            if !autos.contains(&g) {
-                queue.push(g.clone());
-                autos.insert(g);
-            }
-        }
-    }
-
-    autos
-}
-
 pub struct ObstructionSearcher {
     pub found: Vec<Graph32>,
     visited: HashSet<Graph32>,
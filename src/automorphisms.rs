@@ -0,0 +1,337 @@
+//! Permutation groups given by generators, with a base and strong generating
+//! set from the deterministic Schreier–Sims algorithm. Used for the
+//! automorphism groups found by the search tree.
+
+use std::collections::BTreeMap;
+use crate::prelude::*;
+
+/// The elements `u` with `u(base) = point` and their inverses for every point
+/// in the orbit of the base point of one level
+type Transversal<P> = BTreeMap<usize, (P, P)>;
+
+#[derive(Clone, Debug)]
+pub struct AutomorphismGroup<P: Permutation> {
+    points: Vec<usize>,
+    base: Vec<usize>,
+    strong: Vec<P>,
+    transversals: Vec<Transversal<P>>,
+}
+
+fn is_identity<P: Permutation>(perm: &P) -> bool {
+    (0..perm.len()).all(|i| perm.get(i) == i)
+}
+
+fn moved_point<P: Permutation>(perm: &P) -> Option<usize> {
+    (0..perm.len()).find(|&i| perm.get(i) != i)
+}
+
+impl<P: Permutation> AutomorphismGroup<P> {
+    /// The group generated by `generators` acting on `points`, the
+    /// generators must fix every other point
+    pub fn new<I: IntoIterator<Item = P>>(points: Vec<usize>, generators: I) -> Self {
+        Self::with_base(points, generators, &[])
+    }
+
+    /// Like `new` but the base starts with `prefix`, so the strong generators
+    /// fixing its first `k` points generate their pointwise stabilizer
+    fn with_base<I: IntoIterator<Item = P>>(points: Vec<usize>, generators: I, prefix: &[usize]) -> Self {
+        let mut group = Self {
+            points,
+            base: prefix.to_vec(),
+            strong: generators.into_iter().filter(|g| !is_identity(g)).collect(),
+            transversals: Vec::new(),
+        };
+        for i in 0..group.strong.len() {
+            if group.base.iter().all(|&b| group.strong[i].get(b) == b) {
+                let b = moved_point(&group.strong[i]).unwrap();
+                group.base.push(b);
+            }
+        }
+        group.transversals = vec![BTreeMap::new(); group.base.len()];
+        for level in 0..group.base.len() {
+            group.update_transversal(level);
+        }
+        group.schreier_sims();
+        group
+    }
+
+    /// The strong generators fixing the first `level` base points
+    fn level_generators(&self, level: usize) -> impl '_ + Iterator<Item = &P> {
+        self.strong.iter().filter(move |g| self.base[..level].iter().all(|&b| g.get(b) == b))
+    }
+
+    fn update_transversal(&mut self, level: usize) {
+        let b = self.base[level];
+        let mut transversal = BTreeMap::new();
+        transversal.insert(b, (P::new(), P::new()));
+        let mut queue = vec![b];
+        while let Some(p) = queue.pop() {
+            let u = transversal[&p].0.clone();
+            for g in self.level_generators(level) {
+                let q = g.get(p);
+                if let std::collections::btree_map::Entry::Vacant(entry) = transversal.entry(q) {
+                    let v = u.chain(g);
+                    entry.insert((v.clone(), v.invert()));
+                    queue.push(q);
+                }
+            }
+        }
+        self.transversals[level] = transversal;
+    }
+
+    /// Divides `perm` by transversal elements from level `from` on, returns
+    /// the rest and the level where that failed
+    fn strip(&self, mut perm: P, from: usize) -> (P, usize) {
+        for level in from..self.base.len() {
+            match self.transversals[level].get(&perm.get(self.base[level])) {
+                Some((_, inverse)) => perm = perm.chain(inverse),
+                None => return (perm, level),
+            }
+        }
+        (perm, self.base.len())
+    }
+
+    /// Adds strong generators until every Schreier generator of every level
+    /// strips to the identity through the levels below it
+    fn schreier_sims(&mut self) {
+        let mut level = self.base.len();
+        'levels: while level > 0 {
+            let current = level - 1;
+            self.update_transversal(current);
+            let generators: Vec<P> = self.level_generators(current).cloned().collect();
+            let orbit: Vec<(usize, P)> = self.transversals[current].iter().map(|(&p, (u, _))| (p, u.clone())).collect();
+
+            for (p, u) in &orbit {
+                for g in &generators {
+                    let inverse = &self.transversals[current][&g.get(*p)].1;
+                    let schreier = u.chain(g).chain(inverse);
+                    let (rest, failed) = self.strip(schreier, current + 1);
+                    if is_identity(&rest) {
+                        continue
+                    }
+
+                    if failed == self.base.len() {
+                        self.base.push(moved_point(&rest).unwrap());
+                        self.transversals.push(BTreeMap::new());
+                    }
+                    self.strong.push(rest);
+                    for l in current + 1..=failed {
+                        self.update_transversal(l);
+                    }
+                    level = failed + 1;
+                    continue 'levels
+                }
+            }
+            level -= 1;
+        }
+    }
+
+    pub fn points(&self) -> &[usize] {
+        &self.points
+    }
+
+    pub fn base(&self) -> &[usize] {
+        &self.base
+    }
+
+    /// A strong generating set relative to `base`
+    pub fn generators(&self) -> &[P] {
+        &self.strong
+    }
+
+    /// The number of elements, `None` if it does not fit into a `u128`
+    pub fn order(&self) -> Option<u128> {
+        self.transversals.iter().try_fold(1u128, |order, t| order.checked_mul(t.len() as u128))
+    }
+
+    pub fn contains(&self, perm: &P) -> bool {
+        let (rest, _) = self.strip(perm.clone(), 0);
+        is_identity(&rest)
+    }
+
+    /// For every label up to the largest point, the smallest point in its
+    /// orbit for points and the label itself otherwise
+    pub fn representatives(&self) -> Vec<usize> {
+        let n = self.points.iter().max().map_or(0, |&u| u + 1);
+        let mut parent: Vec<usize> = (0..n).collect();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            if parent[i] != i {
+                parent[i] = find(parent, parent[i]);
+            }
+            parent[i]
+        }
+        for g in &self.strong {
+            for &u in &self.points {
+                let (a, b) = (find(&mut parent, u), find(&mut parent, g.get(u)));
+                parent[std::cmp::max(a, b)] = std::cmp::min(a, b);
+            }
+        }
+        (0..n).map(|i| find(&mut parent, i)).collect()
+    }
+
+    /// The orbits of the points, each sorted and ordered by their smallest
+    /// point
+    pub fn orbits(&self) -> Vec<Vec<usize>> {
+        let representatives = self.representatives();
+        let mut orbits: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &u in &self.points {
+            orbits.entry(representatives[u]).or_default().push(u);
+        }
+        orbits.into_values().collect()
+    }
+
+    pub fn orbit(&self, u: usize) -> Vec<usize> {
+        let representatives = self.representatives();
+        self.points.iter().copied().filter(|&v| representatives[v] == representatives[u]).collect()
+    }
+
+    /// The orbits of the edges of `graph` as sorted pairs `u < v`, ordered by
+    /// their smallest edge. `graph` must be invariant under the group.
+    pub fn edge_orbits<G: Graph<Perm = P>>(&self, graph: &G) -> Vec<Vec<(usize, usize)>> {
+        let key = |u: usize, v: usize| (std::cmp::min(u, v), std::cmp::max(u, v));
+        let mut seen = std::collections::BTreeSet::new();
+        let mut orbits = Vec::new();
+        for (u, v) in graph.edges() {
+            if !seen.insert(key(u, v)) {
+                continue
+            }
+            let mut orbit = vec![key(u, v)];
+            let mut i = 0;
+            while i < orbit.len() {
+                let (a, b) = orbit[i];
+                for g in &self.strong {
+                    let image = key(g.get(a), g.get(b));
+                    if seen.insert(image) {
+                        orbit.push(image);
+                    }
+                }
+                i += 1;
+            }
+            orbit.sort_unstable();
+            orbits.push(orbit);
+        }
+        orbits.sort_unstable();
+        orbits
+    }
+
+    /// The subgroup fixing every point of `fixed`
+    pub fn stabilizer(&self, fixed: &[usize]) -> Self {
+        let group = Self::with_base(self.points.clone(), self.strong.clone(), fixed);
+        let generators: Vec<P> = group.level_generators(fixed.len()).cloned().collect();
+        Self::new(self.points.clone(), generators)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::families;
+    use crate::graph::{Graph16, Graph32, DynGraph};
+
+    fn group<G: Graph + Ord>(graph: &G) -> AutomorphismGroup<G::Perm> {
+        crate::iso::search_tree(graph.clone()).automorphism_group()
+    }
+
+    #[test]
+    fn known_groups() {
+        assert_eq!(group(&Graph16::complete(6)).order(), Some(720));
+        assert_eq!(group(&families::cycle::<Graph16>(7).unwrap()).order(), Some(14));
+        assert_eq!(group(&families::petersen::<Graph16>().unwrap()).order(), Some(120));
+        assert_eq!(group(&families::hypercube::<Graph16>(4).unwrap()).order(), Some(384));
+        assert_eq!(group(&families::complete_bipartite::<Graph16>(3, 4).unwrap()).order(), Some(144));
+        assert_eq!(group(&Graph32::complete(30)).order(), Some((1..=30).product()));
+        assert_eq!(group(&families::path::<Graph16>(1).unwrap()).order(), Some(1));
+
+        // The length of dynamic permutations depends on the largest moved point
+        let single = group(&families::path::<DynGraph>(1).unwrap());
+        assert_eq!(single.points(), [0]);
+        assert_eq!(single.orbits(), vec![vec![0]]);
+        assert_eq!(group(&families::petersen::<DynGraph>().unwrap()).order(), Some(120));
+        assert_eq!(group(&families::star::<DynGraph>(5).unwrap()).stabilizer(&[1]).order(), Some(24));
+
+        let star = group(&families::star::<Graph16>(5).unwrap());
+        assert_eq!(star.orbits(), vec![vec![0], vec![1, 2, 3, 4, 5]]);
+        assert_eq!(star.stabilizer(&[1]).order(), Some(24));
+        assert_eq!(star.stabilizer(&[1, 2]).orbit(3), vec![3, 4, 5]);
+    }
+
+    /// A triangle with a pendant path of two nodes at node 0
+    fn pendant_triangle<G: Graph + Ord>() {
+        let mut graph: G = families::cycle(3).unwrap();
+        graph.add_node(3);
+        graph.add_node(4);
+        graph.add_edge(0, 3);
+        graph.add_edge(3, 4);
+        let group = group(&graph);
+        assert_eq!(group.order(), Some(2));
+        assert_eq!(group.orbits(), vec![vec![0], vec![1, 2], vec![3], vec![4]]);
+        assert_eq!(group.representatives()[..5], [0, 1, 1, 3, 4]);
+        assert_eq!(group.edge_orbits(&graph), vec![vec![(0, 1), (0, 2)], vec![(0, 3)], vec![(1, 2)], vec![(3, 4)]]);
+    }
+
+    #[test]
+    fn orbits_and_stabilizers() {
+        pendant_triangle::<Graph16>();
+        pendant_triangle::<DynGraph>();
+
+        // Rigid graphs have only singleton orbits
+        let mut rigid: DynGraph = families::path(5).unwrap();
+        rigid.add_node(5);
+        rigid.add_edge(1, 5);
+        rigid.add_edge(2, 5);
+        let group = self::group(&rigid);
+        assert_eq!(group.order(), Some(1));
+        assert_eq!(group.orbits(), (0..6).map(|u| vec![u]).collect::<Vec<_>>());
+
+        let petersen: Graph16 = families::petersen().unwrap();
+        let group = self::group(&petersen);
+        assert_eq!(group.orbits().len(), 1);
+        assert_eq!(group.edge_orbits(&petersen).len(), 1);
+        assert_eq!(group.stabilizer(&[0]).order(), Some(12));
+        // The node, its neighbours and the nodes at distance two
+        assert_eq!(group.stabilizer(&[0]).orbits().len(), 3);
+    }
+
+    #[test]
+    fn random_graphs() {
+        use crate::permutation::Permutation;
+
+        for _ in 0..30 {
+            let graph: Graph16 = crate::random::graph(7);
+            let group = group(&graph);
+
+            // Count the automorphisms by brute force over all permutations
+            let mut count = 0;
+            let mut perm: Vec<usize> = (0..7).collect();
+            let mut c = [0; 7];
+            let mut i = 0;
+            let mut check = |perm: &[usize]| {
+                let p = <Graph16 as Graph>::Perm::from_iter(perm.iter().copied().enumerate()).unwrap();
+                let mut shuffled = graph;
+                shuffled.shuffle(&p);
+                if shuffled == graph {
+                    count += 1;
+                    assert!(group.contains(&p));
+                }
+            };
+            check(&perm);
+            // Heap's algorithm
+            while i < 7 {
+                if c[i] < i {
+                    if i % 2 == 0 {
+                        perm.swap(0, i);
+                    } else {
+                        perm.swap(c[i], i);
+                    }
+                    check(&perm);
+                    c[i] += 1;
+                    i = 0;
+                } else {
+                    c[i] = 0;
+                    i += 1;
+                }
+            }
+            assert_eq!(group.order(), Some(count));
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Biconnected components, articulation points and the block-cut tree, found
+//! with the lowpoint DFS of Hopcroft and Tarjan.
+
+use crate::prelude::*;
+
+struct Dfs<'a, G: Graph> {
+    graph: &'a G,
+    depth: Vec<usize>,
+    low: Vec<usize>,
+    visited: G::Set,
+    edges: Vec<(usize, usize)>,
+    blocks: Vec<G>,
+    cut_nodes: G::Set,
+}
+
+impl<'a, G: Graph> Dfs<'a, G> {
+    fn new(graph: &'a G) -> Self {
+        Self {
+            graph,
+            depth: vec![0; G::MAXN],
+            low: vec![0; G::MAXN],
+            visited: G::Set::new(),
+            edges: Vec::new(),
+            blocks: Vec::new(),
+            cut_nodes: G::Set::new(),
+        }
+    }
+
+    fn dfs(&mut self, u: usize, parent: Option<usize>, depth: usize) {
+        self.visited.set(u);
+        self.depth[u] = depth;
+        self.low[u] = depth;
+        let mut children = 0;
+
+        for v in self.graph.siblings(u).iter() {
+            if !self.visited.get(v) {
+                children += 1;
+                self.edges.push((u, v));
+                self.dfs(v, Some(u), depth + 1);
+                self.low[u] = std::cmp::min(self.low[u], self.low[v]);
+
+                // Nothing below v reaches above u, so u separates them
+                if self.low[v] >= depth {
+                    if parent.is_some() || children > 1 {
+                        self.cut_nodes.set(u);
+                    }
+                    self.pop_block(u, v);
+                }
+            } else if Some(v) != parent && self.depth[v] < depth {
+                self.edges.push((u, v));
+                self.low[u] = std::cmp::min(self.low[u], self.depth[v]);
+            }
+        }
+    }
+
+    /// Pops the edges above and including `(u, v)` as one block
+    fn pop_block(&mut self, u: usize, v: usize) {
+        let mut block = G::empty();
+        while let Some((a, b)) = self.edges.pop() {
+            block.add_node(a);
+            block.add_node(b);
+            block.add_edge(a, b);
+            if (a, b) == (u, v) {
+                break
+            }
+        }
+        self.blocks.push(block);
+    }
+
+    fn run(mut self) -> (Vec<G>, G::Set) {
+        for u in self.graph.nodes().iter() {
+            if self.visited.get(u) {
+                continue
+            }
+            self.dfs(u, None, 0);
+            // An isolated node is a block on its own
+            if self.graph.siblings(u).is_empty() {
+                let mut block = G::empty();
+                block.add_node(u);
+                self.blocks.push(block);
+            }
+        }
+        (self.blocks, self.cut_nodes)
+    }
+}
+
+/// The nodes whose removal increases the number of components
+pub fn articulation_points<G: Graph>(graph: &G) -> G::Set {
+    Dfs::new(graph).run().1
+}
+
+/// The maximal subgraphs without articulation points, bridges are blocks with
+/// a single edge and isolated nodes blocks without edges
+pub fn blocks<G: Graph>(graph: &G) -> Vec<G> {
+    Dfs::new(graph).run().0
+}
+
+/// Connected with at least three nodes and no articulation point
+pub fn is_biconnected<G: Graph>(graph: &G) -> bool {
+    graph.nodes().count() >= 3 && graph.is_connected() && articulation_points(graph).is_empty()
+}
+
+/// The tree with a node for every block and every articulation point, a
+/// block is adjacent to the articulation points it contains
+pub struct BlockCutTree<G: Graph> {
+    blocks: Vec<G>,
+    cut_nodes: G::Set,
+}
+
+impl<G: Graph> BlockCutTree<G> {
+    pub fn new(graph: &G) -> Self {
+        let (blocks, cut_nodes) = Dfs::new(graph).run();
+        Self {
+            blocks,
+            cut_nodes,
+        }
+    }
+
+    pub fn blocks(&self) -> &[G] {
+        &self.blocks
+    }
+
+    pub fn cut_nodes(&self) -> G::Set {
+        self.cut_nodes.clone()
+    }
+
+    /// The articulation points in block `i`
+    pub fn block_cut_nodes(&self, i: usize) -> G::Set {
+        self.blocks[i].nodes().intersection(&self.cut_nodes)
+    }
+
+    /// The blocks containing node `u`, more than one exactly when `u` is an
+    /// articulation point
+    pub fn blocks_at(&self, u: usize) -> impl '_ + Iterator<Item = usize> {
+        self.blocks.iter().enumerate()
+            .filter(move |(_, block)| block.has_node(u))
+            .map(|(i, _)| i)
+    }
+
+    /// The edges of the tree as pairs of a block index and an articulation point
+    pub fn edges(&self) -> impl '_ + Iterator<Item = (usize, usize)> {
+        (0..self.blocks.len()).flat_map(move |i| {
+            self.block_cut_nodes(i).iter().map(move |u| (i, u))
+        })
+    }
+
+    /// Blocks with at most one articulation point, the leaves of the tree
+    pub fn leaf_blocks(&self) -> impl '_ + Iterator<Item = usize> {
+        (0..self.blocks.len()).filter(move |&i| self.block_cut_nodes(i).count() <= 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32};
+
+    fn from_edges(n: usize, edges: &[(usize, usize)]) -> Graph16 {
+        let mut graph = Graph16::empty();
+        for u in 0..n {
+            graph.add_node(u);
+        }
+        for &(u, v) in edges {
+            graph.add_edge(u, v);
+        }
+        graph
+    }
+
+    #[test]
+    fn bowtie_and_path() {
+        let bowtie = from_edges(5, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 2)]);
+        let tree = BlockCutTree::new(&bowtie);
+        assert_eq!(tree.blocks().len(), 2);
+        assert_eq!(tree.cut_nodes().iter().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(tree.blocks_at(2).count(), 2);
+        assert_eq!(tree.edges().count(), 2);
+        assert!(tree.blocks().iter().all(|block| block.edges_count() == 3));
+
+        let path: Graph16 = crate::families::path(4).unwrap();
+        let tree = BlockCutTree::new(&path);
+        assert_eq!(tree.blocks().len(), 3);
+        assert_eq!(tree.cut_nodes().iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tree.leaf_blocks().count(), 2);
+
+        assert!(is_biconnected(&Graph16::complete(4)));
+        assert!(!is_biconnected(&bowtie));
+        assert!(articulation_points(&Graph16::complete(4)).is_empty());
+    }
+
+    #[test]
+    fn disconnected() {
+        let graph = from_edges(6, &[(0, 1), (1, 2), (2, 0), (3, 4)]);
+        let blocks = blocks(&graph);
+        assert_eq!(blocks.len(), 3);
+        assert!(blocks.iter().any(|block| block.nodes().iter().eq(vec![5])));
+        assert!(articulation_points(&graph).is_empty());
+    }
+
+    #[test]
+    fn random_graphs() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let original: Graph32 = crate::random::graph(20);
+            let mut graph = original;
+            for (u, v) in original.edges() {
+                if rng.gen_bool(0.85) {
+                    graph.del_edge(u, v);
+                }
+            }
+
+            let components = graph.components().count();
+            let cut_nodes = articulation_points(&graph);
+            for u in graph.nodes().iter() {
+                let mut without = graph;
+                without.del_node(u);
+                let isolated = graph.siblings(u).is_empty();
+                let separates = without.components().count() + isolated as usize > components;
+                assert_eq!(cut_nodes.get(u), separates);
+            }
+
+            // Every edge is in exactly one block and every block is biconnected or
+            // a single edge or node
+            let blocks = blocks(&graph);
+            let edges: usize = blocks.iter().map(|block| block.edges_count()).sum();
+            assert_eq!(edges, graph.edges_count());
+            for block in &blocks {
+                assert!(block.nodes().count() <= 2 || is_biconnected(block));
+            }
+        }
+    }
+}
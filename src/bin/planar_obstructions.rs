@@ -27,7 +27,7 @@ fn main() {
 
     let obstructions: Vec<_> = obstructions.into_iter().collect();
 
-    sgtk::viz::render_dot("test.pdf", &obstructions);
+    sgtk::viz::render_svg("test.svg", &obstructions).unwrap();
 
     /*
     let mut graph = sgtk::random::graph16(10);
@@ -1,5 +1,5 @@
 use std::collections::HashSet;
-use crate::permutation::{Permutation, SmallPerm};
+use crate::permutation::{Permutation, SmallPerm, DynPerm};
 
 pub trait Intset {
     fn new() -> Self;
@@ -60,6 +60,7 @@ pub trait Bitset: Intset + Eq + Clone {
 macro_rules! bit_set {
     ($name:ident, $size:expr, $type:ty, $from_ty:ident, $to_ty:ident, $iter:ident, $iter_enum:ident) => (
         #[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+        #[repr(transparent)]
         pub struct $name {
             bitset: $type,
         }
@@ -351,8 +352,7 @@ impl Intset for DynIntSet {
     }
 
     fn smallest(&self) -> Option<usize> {
-        // FIXME: this is not correct!!!
-        self.inner.iter().next().copied()
+        self.inner.iter().min().copied()
     }
 
     fn count(&self) -> usize {
@@ -368,3 +368,333 @@ impl<'a> IntoIterator for &'a DynIntSet {
         self.inner.iter().copied()
     }
 }
+
+/// Largest number of elements in a `DynBitset`, this is the universe used by
+/// `invert` and `mask_ge`.
+pub const DYN_MAXN: usize = 1024;
+
+/// Heap allocated bitset, only the words up to the largest element are stored.
+#[derive(Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DynBitset {
+    words: Vec<u64>,
+}
+
+impl DynBitset {
+    #[inline]
+    fn normalize(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+
+    #[inline]
+    fn zip_with<F: Fn(u64, u64) -> u64>(&self, other: &Self, f: F) -> Self {
+        let len = std::cmp::max(self.words.len(), other.words.len());
+        let mut res = Self {
+            words: (0..len).map(|i| {
+                f(self.words.get(i).copied().unwrap_or(0), other.words.get(i).copied().unwrap_or(0))
+            }).collect(),
+        };
+        res.normalize();
+        res
+    }
+}
+
+impl Intset for DynBitset {
+    fn new() -> Self {
+        Self {
+            words: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> bool {
+        self.words.get(i / 64).map(|w| w & (1 << (i % 64)) > 0).unwrap_or(false)
+    }
+
+    #[inline]
+    fn set_val(&mut self, i: usize, v: bool) {
+        debug_assert!(i < DYN_MAXN);
+        if v {
+            if self.words.len() <= i / 64 {
+                self.words.resize(i / 64 + 1, 0);
+            }
+            self.words[i / 64] |= 1 << (i % 64);
+        } else if let Some(w) = self.words.get_mut(i / 64) {
+            *w &= !(1 << (i % 64));
+            self.normalize();
+        }
+    }
+
+    #[inline]
+    fn smallest(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    #[inline]
+    fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+impl Bitset for DynBitset {
+    const SIZE: usize = DYN_MAXN;
+
+    type Iter = DynIter;
+
+    type Enumerate = DynIterEnumerate;
+
+    type Perm = DynPerm;
+
+    #[inline]
+    fn swap(&mut self, i: usize, j: usize) {
+        let vi = self.get(i);
+        let vj = self.get(j);
+        self.set_val(i, vj);
+        self.set_val(j, vi);
+    }
+
+    #[inline]
+    fn mask_le(n: usize) -> Self {
+        let mut words = vec![u64::MAX; n / 64];
+        if !n.is_multiple_of(64) {
+            words.push((1 << (n % 64)) - 1);
+        }
+        Self {
+            words,
+        }
+    }
+
+    #[inline]
+    fn mask_ge(n: usize) -> Self {
+        Self::mask_le(n).invert()
+    }
+
+    #[inline]
+    fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    #[inline]
+    fn intersection(&self, other: &Self) -> Self {
+        let mut res = Self {
+            words: self.words.iter().zip(&other.words).map(|(a, b)| a & b).collect(),
+        };
+        res.normalize();
+        res
+    }
+
+    #[inline]
+    fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    #[inline]
+    fn invert(&self) -> Self {
+        let mut res = Self {
+            words: (0..DYN_MAXN / 64).map(|i| !self.words.get(i).copied().unwrap_or(0)).collect(),
+        };
+        res.normalize();
+        res
+    }
+
+    #[inline]
+    fn is_superset(&self, other: &Self) -> bool {
+        other.words.iter().enumerate()
+            .all(|(i, w)| self.words.get(i).copied().unwrap_or(0) & w == *w)
+    }
+
+    #[inline]
+    fn enumerate(maxn: usize) -> DynIterEnumerate {
+        Self::enumerate_mask(Self::mask_le(maxn))
+    }
+
+    #[inline]
+    fn enumerate_mask(mask: Self) -> DynIterEnumerate {
+        DynIterEnumerate {
+            mask: mask.iter().collect(),
+            curr: Some(Self::new()),
+        }
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &DynPerm) {
+        let old = std::mem::take(self);
+        for i in old.iter() {
+            self.set(permutation.get(i));
+        }
+    }
+
+    #[inline]
+    fn iter(&self) -> DynIter {
+        DynIter {
+            words: self.words.clone(),
+            front: 0,
+            back: self.words.len(),
+        }
+    }
+}
+
+impl IntoIterator for DynBitset {
+    type Item = usize;
+    type IntoIter = DynIter;
+
+    #[inline]
+    fn into_iter(self) -> DynIter {
+        let back = self.words.len();
+        DynIter {
+            words: self.words,
+            front: 0,
+            back,
+        }
+    }
+}
+
+impl IntoIterator for &DynBitset {
+    type Item = usize;
+    type IntoIter = DynIter;
+
+    #[inline]
+    fn into_iter(self) -> DynIter {
+        self.iter()
+    }
+}
+
+impl std::fmt::Debug for DynBitset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct DynIter {
+    words: Vec<u64>,
+    front: usize,
+    back: usize,
+}
+
+impl Iterator for DynIter {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            let word = &mut self.words[self.front];
+            if *word != 0 {
+                let i = word.trailing_zeros() as usize;
+                *word ^= 1 << i;
+                return Some(self.front * 64 + i)
+            }
+            self.front += 1;
+        }
+        None
+    }
+}
+
+impl std::iter::DoubleEndedIterator for DynIter {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            let word = &mut self.words[self.back - 1];
+            if *word != 0 {
+                let i = 63 - word.leading_zeros() as usize;
+                *word ^= 1 << i;
+                return Some((self.back - 1) * 64 + i)
+            }
+            self.back -= 1;
+        }
+        None
+    }
+}
+
+pub struct DynIterEnumerate {
+    mask: Vec<usize>,
+    curr: Option<DynBitset>,
+}
+
+impl Iterator for DynIterEnumerate {
+    type Item = DynBitset;
+
+    #[inline]
+    fn next(&mut self) -> Option<DynBitset> {
+        let res = self.curr.take()?;
+
+        // Binary increment where the bits are the elements of the mask
+        if let Some(k) = self.mask.iter().position(|i| !res.get(*i)) {
+            let mut next = res.clone();
+            for i in &self.mask[..k] {
+                next.clear(*i);
+            }
+            next.set(self.mask[k]);
+            self.curr = Some(next);
+        }
+
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dyn_int_set_smallest() {
+        let mut set = DynIntSet::new();
+        assert_eq!(set.smallest(), None);
+        for &i in &[40, 7, 93, 12, 3, 58, 21] {
+            set.set(i);
+        }
+        assert_eq!(set.smallest(), Some(3));
+        set.set_val(3, false);
+        assert_eq!(set.smallest(), Some(7));
+    }
+
+    #[test]
+    fn dyn_bitset_matches_bitset128() {
+        let elements = [0, 3, 63, 64, 65, 100, 127];
+        let mut a = DynBitset::new();
+        let mut b = Bitset128::new();
+        for i in &elements {
+            a.set(*i);
+            b.set(*i);
+        }
+        assert_eq!(a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+        assert_eq!(a.iter().rev().collect::<Vec<_>>(), b.iter().rev().collect::<Vec<_>>());
+        assert_eq!(a.count(), elements.len());
+        assert_eq!(a.smallest(), Some(0));
+
+        let mask = DynBitset::mask_le(70);
+        assert_eq!(mask.count(), 70);
+        assert_eq!(a.intersection(&mask).iter().collect::<Vec<_>>(), vec![0, 3, 63, 64, 65]);
+        assert_eq!(a.difference(&mask).iter().collect::<Vec<_>>(), vec![100, 127]);
+        assert_eq!(a.invert().count(), DYN_MAXN - elements.len());
+        assert_eq!(a.invert().invert(), a);
+        assert!(a.union(&mask).is_superset(&a));
+        assert!(!a.is_superset(&mask));
+
+        for i in &elements {
+            a.clear(*i);
+        }
+        assert!(a.is_empty());
+        assert_eq!(a, DynBitset::new());
+    }
+
+    #[test]
+    fn dyn_bitset_enumerate() {
+        let mut mask = DynBitset::new();
+        mask.set(1);
+        mask.set(70);
+        mask.set(200);
+        let subsets: Vec<_> = DynBitset::enumerate_mask(mask.clone()).collect();
+        assert_eq!(subsets.len(), 8);
+        assert!(subsets.iter().all(|s| mask.is_superset(s)));
+        assert_eq!(subsets.last(), Some(&mask));
+
+        assert_eq!(DynBitset::enumerate(4).count(), 16);
+    }
+}
@@ -0,0 +1,368 @@
+//! Subgraph, induced subgraph and minor containment. Mappings go from the
+//! nodes of the pattern to the nodes of the host.
+
+use std::collections::{BTreeMap, BTreeSet};
+use crate::prelude::*;
+
+/// VF2-style backtracking, the pattern is matched in an order where every node
+/// after the first of its component has a matched neighbour, so the host
+/// candidates are an intersection of sibling sets
+struct Matcher<'a, G: Graph> {
+    host: &'a G,
+    /// Pattern nodes in matching order with their degree and the positions
+    /// of their earlier neighbours and non-neighbours
+    order: Vec<(usize, usize, Vec<usize>, Vec<usize>)>,
+    induced: bool,
+    images: Vec<usize>,
+    used: G::Set,
+}
+
+impl<'a, G: Graph> Matcher<'a, G> {
+    fn new<H: Graph>(host: &'a G, pattern: &H, induced: bool) -> Self {
+        let mut order: Vec<usize> = Vec::new();
+        let mut rest: Vec<usize> = pattern.nodes().iter().collect();
+        while !rest.is_empty() {
+            let (i, _) = rest.iter().enumerate()
+                .max_by_key(|&(_, &u)| {
+                    let matched = order.iter().filter(|&&v| pattern.has_edge(u, v)).count();
+                    (matched, pattern.siblings(u).count())
+                })
+                .unwrap();
+            order.push(rest.swap_remove(i));
+        }
+
+        let order = order.iter().enumerate()
+            .map(|(i, &u)| {
+                let (before, not_before) = (0..i).partition(|&j| pattern.has_edge(u, order[j]));
+                (u, pattern.siblings(u).count(), before, not_before)
+            })
+            .collect();
+        Self {
+            host,
+            order,
+            induced,
+            images: Vec::new(),
+            used: G::Set::new(),
+        }
+    }
+
+    fn search(&mut self) -> bool {
+        let k = self.images.len();
+        if k == self.order.len() {
+            return true
+        }
+
+        let mut candidates = self.host.nodes().difference(&self.used);
+        for &j in &self.order[k].2 {
+            candidates = candidates.intersection(&self.host.siblings(self.images[j]));
+        }
+        if self.induced {
+            for &j in &self.order[k].3 {
+                candidates = candidates.difference(&self.host.siblings(self.images[j]));
+            }
+        }
+
+        for v in candidates.iter() {
+            if self.host.siblings(v).count() < self.order[k].1 {
+                continue
+            }
+            self.images.push(v);
+            self.used.set(v);
+            if self.search() {
+                return true
+            }
+            self.used.clear(v);
+            self.images.pop();
+        }
+        false
+    }
+
+    fn run(mut self) -> Option<BTreeMap<usize, usize>> {
+        if self.order.len() > self.host.nodes().count() || !self.search() {
+            return None
+        }
+        Some(self.order.iter().map(|o| o.0).zip(self.images).collect())
+    }
+}
+
+/// An injective mapping of the nodes of `pattern` into `host` that maps edges
+/// to edges
+pub fn find_subgraph<G: Graph, H: Graph>(host: &G, pattern: &H) -> Option<BTreeMap<usize, usize>> {
+    Matcher::new(host, pattern, false).run()
+}
+
+/// An injective mapping of the nodes of `pattern` into `host` that maps edges
+/// to edges and non-edges to non-edges
+pub fn find_induced_subgraph<G: Graph, H: Graph>(host: &G, pattern: &H) -> Option<BTreeMap<usize, usize>> {
+    Matcher::new(host, pattern, true).run()
+}
+
+pub fn is_subgraph<G: Graph, H: Graph>(host: &G, pattern: &H) -> bool {
+    find_subgraph(host, pattern).is_some()
+}
+
+pub fn is_induced_subgraph<G: Graph, H: Graph>(host: &G, pattern: &H) -> bool {
+    find_induced_subgraph(host, pattern).is_some()
+}
+
+/// `edges - nodes + components`, deleting and contracting never increase it
+fn cyclomatic_number<G: Graph>(graph: &G) -> usize {
+    graph.edges_count() + graph.clone().components().count() - graph.nodes().count()
+}
+
+/// Deletes and contracts in the host until the pattern is a subgraph. Graphs
+/// that failed are remembered by their canonical form, and by their labels to
+/// skip the canonical form for the same contractions in a different order.
+struct MinorSearch<'a, H: Graph, G: Graph + Ord> {
+    pattern: &'a H,
+    nodes: usize,
+    edges: usize,
+    min_degree: usize,
+    cyclomatic_number: usize,
+    degrees: Vec<usize>,
+    /// Planar graphs only have planar minors
+    planar: bool,
+    failed: BTreeSet<G>,
+    failed_labeled: BTreeSet<G>,
+}
+
+impl<'a, H: Graph, G: Graph + Ord> MinorSearch<'a, H, G> {
+    fn new(pattern: &'a H) -> Self {
+        Self {
+            pattern,
+            nodes: pattern.nodes().count(),
+            edges: pattern.edges_count(),
+            min_degree: crate::invariants::min_degree(pattern),
+            cyclomatic_number: cyclomatic_number(pattern),
+            degrees: crate::invariants::degree_sequence(pattern),
+            planar: pattern.is_planar(),
+            failed: BTreeSet::new(),
+            failed_labeled: BTreeSet::new(),
+        }
+    }
+
+    /// Deletes nodes of degree at most one and contracts nodes of degree two
+    /// into a neighbour as long as no pattern node could be mapped to them.
+    /// A contracted node is a leaf of any branch set it could have been in,
+    /// so moving it to the neighbour keeps both connected.
+    fn reduce(&self, host: &mut G, branches: &mut [G::Set]) {
+        while let Some(u) = host.nodes().iter().find(|&u| {
+            let degree = host.siblings(u).count();
+            degree < self.min_degree && degree <= 2
+        }) {
+            match host.siblings(u).smallest() {
+                Some(v) if host.siblings(u).count() == 2 => {
+                    host.contract_edge(v, u);
+                    branches[v] = branches[v].union(&branches[u]);
+                }
+                _ => host.del_node(u),
+            }
+        }
+    }
+
+    /// Can the largest degrees of `host` cover those of the pattern
+    fn degrees_fit(&self, host: &G) -> bool {
+        let degrees = crate::invariants::degree_sequence(host);
+        degrees.len() >= self.degrees.len() && degrees.iter().zip(&self.degrees).all(|(d, p)| d >= p)
+    }
+
+    fn fail(&mut self, labeled: &[&G], canonical: G) -> Option<BTreeMap<usize, G::Set>> {
+        self.failed_labeled.extend(labeled.iter().map(|&graph| graph.clone()));
+        self.failed.insert(canonical);
+        None
+    }
+
+    fn search(&mut self, unreduced: &G, branches: &[G::Set]) -> Option<BTreeMap<usize, G::Set>> {
+        if self.failed_labeled.contains(unreduced) {
+            return None
+        }
+        let (mut host, mut branches) = (unreduced.clone(), branches.to_vec());
+        self.reduce(&mut host, &mut branches);
+        let (host, branches) = (&host, &branches[..]);
+        if host.nodes().count() < self.nodes || host.edges_count() < self.edges
+            || cyclomatic_number(host) < self.cyclomatic_number || self.failed_labeled.contains(host) {
+            return None
+        }
+        let canonical = host.clone().to_canonical();
+        if self.failed.contains(&canonical) || (!self.planar && host.is_planar()) {
+            return self.fail(&[unreduced, host], canonical)
+        }
+        if self.degrees_fit(host) {
+            if let Some(mapping) = find_subgraph(host, self.pattern) {
+                return Some(mapping.into_iter().map(|(u, v)| (u, branches[v].clone())).collect())
+            }
+        }
+
+        // A node of too small degree for every pattern node is either unused
+        // or shares its branch set with a neighbour, otherwise any edge may be
+        // contracted
+        let u = host.nodes().iter().min_by_key(|&u| host.siblings(u).count()).unwrap();
+        let contractions: Vec<_> = if host.siblings(u).count() < self.min_degree {
+            let mut deleted = host.clone();
+            deleted.del_node(u);
+            if let Some(model) = self.search(&deleted, branches) {
+                return Some(model)
+            }
+            host.siblings(u).iter().map(|v| (v, u)).collect()
+        } else {
+            host.edges().collect()
+        };
+        for (u, v) in contractions {
+            let mut contracted = host.clone();
+            contracted.contract_edge(u, v);
+            let mut branches = branches.to_vec();
+            branches[u] = branches[u].union(&branches[v]);
+            if let Some(model) = self.search(&contracted, &branches) {
+                return Some(model)
+            }
+        }
+        self.fail(&[unreduced, host], canonical)
+    }
+}
+
+/// A minor model of `pattern` in `host`: disjoint connected branch sets of
+/// host nodes for the pattern nodes, with an edge between the branch sets of
+/// adjacent pattern nodes
+pub fn find_minor<G: Graph + Ord, H: Graph>(host: &G, pattern: &H) -> Option<BTreeMap<usize, G::Set>> {
+    let branches: Vec<_> = (0..G::MAXN)
+        .map(|u| {
+            let mut branch = G::Set::new();
+            branch.set(u);
+            branch
+        })
+        .collect();
+    MinorSearch::new(pattern).search(host, &branches)
+}
+
+pub fn is_minor<G: Graph + Ord, H: Graph>(host: &G, pattern: &H) -> bool {
+    find_minor(host, pattern).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::families;
+    use crate::graph::{Graph16, Graph32};
+
+    fn check_model(host: &Graph16, pattern: &Graph16, model: &BTreeMap<usize, <Graph16 as Graph>::Set>) {
+        let mut covered = <Graph16 as Graph>::Set::new();
+        for (&u, branch) in model {
+            assert!(pattern.has_node(u));
+            assert!(covered.intersection(branch).is_empty());
+            assert!(host.subgraph(branch).is_connected());
+            covered = covered.union(branch);
+        }
+        for (u, v) in pattern.edges() {
+            assert!(host.edges().any(|(a, b)| {
+                (model[&u].get(a) && model[&v].get(b)) || (model[&u].get(b) && model[&v].get(a))
+            }));
+        }
+    }
+
+    #[test]
+    fn subgraphs() {
+        let cube: Graph16 = families::hypercube(3).unwrap();
+        let c6: Graph16 = families::cycle(6).unwrap();
+        let c8: Graph16 = families::cycle(8).unwrap();
+        assert!(is_subgraph(&cube, &c8));
+        assert!(!is_induced_subgraph(&cube, &c8));
+        assert!(is_induced_subgraph(&cube, &c6));
+        assert!(!is_subgraph(&cube, &families::cycle::<Graph16>(3).unwrap()));
+
+        let petersen: Graph16 = families::petersen().unwrap();
+        let mapping = find_subgraph(&petersen, &families::cycle::<Graph16>(9).unwrap()).unwrap();
+        for u in 0..9 {
+            assert!(petersen.has_edge(mapping[&u], mapping[&((u + 1) % 9)]));
+        }
+        assert!(!is_subgraph(&petersen, &families::cycle::<Graph16>(4).unwrap()));
+    }
+
+    /// All injections of `0..k` into `0..n`
+    fn injections(k: usize, n: usize) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return vec![Vec::new()]
+        }
+        let mut all = Vec::new();
+        for image in injections(k - 1, n) {
+            for v in (0..n).filter(|v| !image.contains(v)) {
+                let mut image = image.clone();
+                image.push(v);
+                all.push(image);
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn random_subgraphs() {
+        for _ in 0..50 {
+            let host: Graph16 = crate::random::graph(7);
+            let pattern: Graph16 = crate::random::graph(4);
+
+            let (mut subgraph, mut induced) = (false, false);
+            for image in injections(4, 7) {
+                let edges = pattern.edges().all(|(u, v)| host.has_edge(image[u], image[v]));
+                let non_edges = (0..4).all(|u| (0..u).all(|v| pattern.has_edge(u, v) || !host.has_edge(image[u], image[v])));
+                subgraph |= edges;
+                induced |= edges && non_edges;
+            }
+            assert_eq!(is_subgraph(&host, &pattern), subgraph);
+            assert_eq!(is_induced_subgraph(&host, &pattern), induced);
+        }
+    }
+
+    #[test]
+    fn minors() {
+        let petersen: Graph16 = families::petersen().unwrap();
+        let k5 = Graph16::complete(5);
+        let k33: Graph16 = families::complete_bipartite(3, 3).unwrap();
+        assert!(!is_subgraph(&petersen, &k5));
+        check_model(&petersen, &k5, &find_minor(&petersen, &k5).unwrap());
+        check_model(&petersen, &k33, &find_minor(&petersen, &k33).unwrap());
+
+        // Every edge of K5 subdivided, the branch sets pick up the new nodes
+        let mut subdivided = Graph16::complete(5);
+        for (i, (u, v)) in Graph16::complete(5).edges().enumerate() {
+            subdivided.del_edge(u, v);
+            subdivided.add_node(5 + i);
+            subdivided.add_edge(u, 5 + i);
+            subdivided.add_edge(5 + i, v);
+        }
+        let model = find_minor(&subdivided, &k5).unwrap();
+        check_model(&subdivided, &k5, &model);
+        assert!(model.values().any(|branch| branch.count() > 1));
+        assert!(!is_subgraph(&subdivided, &k5));
+
+        // Grids are planar
+        let grid: Graph16 = families::grid(4, 4).unwrap();
+        assert!(!is_minor(&grid, &k5));
+        assert!(!is_minor(&grid, &k33));
+        assert!(is_minor(&grid, &Graph16::complete(4)));
+        let grid: Graph32 = families::grid(5, 5).unwrap();
+        assert!(!is_minor(&grid, &k5));
+        assert!(!is_minor(&grid, &k33));
+
+        // Contracting the Petersen graph to six nodes loses edges
+        assert!(!is_minor(&petersen, &Graph16::complete(6)));
+
+        let path: Graph16 = families::path(6).unwrap();
+        assert!(!is_minor(&path, &families::cycle::<Graph16>(3).unwrap()));
+    }
+
+    #[test]
+    fn random_minors() {
+        // Wagner's theorem, non-planar graphs have a K5 or K3,3 minor
+        let k5 = Graph16::complete(5);
+        let k33: Graph16 = families::complete_bipartite(3, 3).unwrap();
+        for _ in 0..30 {
+            let host: Graph16 = crate::random::graph(9);
+            let models: Vec<_> = [&k5, &k33].iter()
+                .filter_map(|pattern| find_minor(&host, *pattern).map(|model| (*pattern, model)))
+                .collect();
+            assert_eq!(models.is_empty(), host.is_planar());
+            for (pattern, model) in &models {
+                check_model(&host, pattern, model);
+            }
+        }
+    }
+}
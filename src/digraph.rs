@@ -0,0 +1,470 @@
+use crate::bitset::{self, Intset, Bitset};
+use crate::seq::{self, Seq};
+use crate::permutation::Permutation;
+use crate::graph::{Graph, Coloring, SmallColoring};
+
+pub type Digraph16 = BitsetDigraph<bitset::Bitset16, 16>;
+pub type Digraph32 = BitsetDigraph<bitset::Bitset32, 32>;
+pub type Digraph64 = BitsetDigraph<bitset::Bitset64, 64>;
+
+/// Directed graphs without loops or parallel arcs, arcs in both directions
+/// between two nodes are allowed.
+pub trait Digraph: Sized + Clone {
+    const MAXN: usize;
+    type Perm: Permutation;
+    type Set: Bitset<Perm = Self::Perm>;
+    type Path: Seq;
+    type Coloring: Coloring<Set = Self::Set, Perm = Self::Perm>;
+
+    fn empty() -> Self;
+
+    fn is_empty(&self) -> bool;
+
+    /// Every pair of nodes is joined by arcs in both directions
+    fn complete(n: usize) -> Self {
+        let mut digraph = Self::empty();
+        for i in 0..n {
+            digraph.add_node(i);
+        }
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    digraph.add_arc(i, j);
+                }
+            }
+        }
+        digraph
+    }
+
+    fn add_node(&mut self, u: usize);
+
+    fn del_node(&mut self, u: usize);
+
+    fn has_node(&self, u: usize) -> bool;
+
+    fn nodes(&self) -> Self::Set;
+
+    fn add_arc(&mut self, u: usize, v: usize);
+
+    fn del_arc(&mut self, u: usize, v: usize);
+
+    fn has_arc(&self, u: usize, v: usize) -> bool;
+
+    /// Heads of the arcs leaving `u`
+    fn out_neighbours(&self, u: usize) -> Self::Set;
+
+    /// Tails of the arcs entering `u`
+    fn in_neighbours(&self, u: usize) -> Self::Set;
+
+    fn shuffle(&mut self, permutation: &Self::Perm);
+
+    fn subgraph(&self, selected: &Self::Set) -> Self;
+
+    fn out_degree(&self, u: usize) -> usize {
+        self.out_neighbours(u).count()
+    }
+
+    fn in_degree(&self, u: usize) -> usize {
+        self.in_neighbours(u).count()
+    }
+
+    fn arcs(&self) -> ArcIter<'_, Self> {
+        ArcIter {
+            g: self,
+            u: 0,
+            iter_u: self.nodes().iter(),
+            iter_v: Self::Set::new().iter(),
+        }
+    }
+
+    fn arcs_count(&self) -> usize {
+        self.nodes().iter().map(|u| self.out_degree(u)).sum()
+    }
+
+    /// The same digraph with every arc pointing the other way
+    fn reverse(&self) -> Self {
+        let mut digraph = Self::empty();
+        for u in self.nodes().iter() {
+            digraph.add_node(u);
+        }
+        for (u, v) in self.arcs() {
+            digraph.add_arc(v, u);
+        }
+        digraph
+    }
+
+    /// The undirected graph with an edge wherever there is an arc in either
+    /// direction
+    fn underlying<G: Graph>(&self) -> G {
+        let mut graph = G::empty();
+        for u in self.nodes().iter() {
+            graph.add_node(u);
+        }
+        for (u, v) in self.arcs() {
+            graph.add_edge(u, v);
+        }
+        graph
+    }
+
+    /// Every edge of `graph` becomes a pair of opposite arcs
+    fn from_graph<G: Graph>(graph: &G) -> Self {
+        let mut digraph = Self::empty();
+        for u in graph.nodes().iter() {
+            digraph.add_node(u);
+        }
+        for (u, v) in graph.edges() {
+            digraph.add_arc(u, v);
+            digraph.add_arc(v, u);
+        }
+        digraph
+    }
+
+    fn convert<D: Digraph>(&self) -> D {
+        let mut digraph = D::empty();
+        for u in self.nodes().iter() {
+            digraph.add_node(u);
+        }
+        for (u, v) in self.arcs() {
+            digraph.add_arc(u, v);
+        }
+        digraph
+    }
+
+    /// Is there an arc in at most one direction between any two nodes
+    fn is_oriented(&self) -> bool {
+        self.nodes().iter().all(|u| {
+            self.out_neighbours(u).intersection(&self.in_neighbours(u)).is_empty()
+        })
+    }
+
+    /// Is there an arc in exactly one direction between any two nodes
+    fn is_tournament(&self) -> bool {
+        let nodes = self.nodes();
+        self.is_oriented() && nodes.iter().all(|u| {
+            self.out_degree(u) + self.in_degree(u) + 1 == nodes.count()
+        })
+    }
+
+    fn to_canonical(self) -> Self
+        where Self: crate::iso::Refinable
+    {
+        crate::iso::search_tree(self).canonical_graph
+    }
+
+    fn is_canonical(&self) -> bool
+        where Self: crate::iso::Refinable
+    {
+        self == &self.clone().to_canonical()
+    }
+}
+
+pub struct ArcIter<'a, D: Digraph> {
+    g: &'a D,
+    u: usize,
+    iter_u: <D::Set as Bitset>::Iter,
+    iter_v: <D::Set as Bitset>::Iter,
+}
+
+impl<'a, D: Digraph> Iterator for ArcIter<'a, D> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        loop {
+            if let Some(v) = self.iter_v.next() {
+                return Some((self.u, v))
+            }
+            self.u = self.iter_u.next()?;
+            self.iter_v = self.g.out_neighbours(self.u).iter();
+        }
+    }
+}
+
+/// Row `u` of `out` holds the heads of the arcs leaving `u` and row `u` of
+/// `inc` the tails of the arcs entering it. A node is present when its own
+/// bit is set in both rows, like the diagonal of `BitsetGraph`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct BitsetDigraph<B, const N: usize> {
+    out: [B; N],
+    inc: [B; N],
+}
+
+impl<B: Bitset + Copy, const N: usize> Digraph for BitsetDigraph<B, N> {
+    const MAXN: usize = N;
+    type Perm = B::Perm;
+    type Set = B;
+    type Path = seq::SmallSeq<N>;
+    type Coloring = SmallColoring<B, N>;
+
+    fn empty() -> Self {
+        Self {
+            out: [B::new(); N],
+            inc: [B::new(); N],
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.out.iter().all(|row| row.is_empty())
+    }
+
+    #[inline]
+    fn add_node(&mut self, u: usize) {
+        self.out[u].set(u);
+        self.inc[u].set(u);
+    }
+
+    #[inline]
+    fn del_node(&mut self, u: usize) {
+        self.out[u] = B::new();
+        self.inc[u] = B::new();
+        for i in 0..N {
+            self.out[i].clear(u);
+            self.inc[i].clear(u);
+        }
+    }
+
+    #[inline]
+    fn has_node(&self, u: usize) -> bool {
+        self.out[u].get(u)
+    }
+
+    #[inline]
+    fn nodes(&self) -> B {
+        let mut res = B::new();
+        for i in 0..N {
+            if self.has_node(i) {
+                res.set(i);
+            }
+        }
+        res
+    }
+
+    #[inline]
+    fn add_arc(&mut self, u: usize, v: usize) {
+        debug_assert!(u != v);
+        debug_assert!(self.has_node(u));
+        debug_assert!(self.has_node(v));
+        self.out[u].set(v);
+        self.inc[v].set(u);
+    }
+
+    #[inline]
+    fn del_arc(&mut self, u: usize, v: usize) {
+        debug_assert!(u != v);
+        self.out[u].clear(v);
+        self.inc[v].clear(u);
+    }
+
+    #[inline]
+    fn has_arc(&self, u: usize, v: usize) -> bool {
+        u != v && self.out[u].get(v)
+    }
+
+    #[inline]
+    fn out_neighbours(&self, u: usize) -> B {
+        let mut res = self.out[u];
+        res.clear(u);
+        res
+    }
+
+    #[inline]
+    fn in_neighbours(&self, u: usize) -> B {
+        let mut res = self.inc[u];
+        res.clear(u);
+        res
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &Self::Perm) {
+        let (out, inc) = (self.out, self.inc);
+
+        for (i, j) in permutation.iter() {
+            let mut row = out[i];
+            row.shuffle(permutation);
+            self.out[j] = row;
+
+            let mut row = inc[i];
+            row.shuffle(permutation);
+            self.inc[j] = row;
+        }
+    }
+
+    #[inline]
+    fn subgraph(&self, selected: &B) -> Self {
+        let mut new = Self::empty();
+        for i in selected.iter() {
+            new.out[i] = self.out[i].intersection(selected);
+            new.inc[i] = self.inc[i].intersection(selected);
+        }
+        new
+    }
+
+    #[inline]
+    fn reverse(&self) -> Self {
+        Self {
+            out: self.inc,
+            inc: self.out,
+        }
+    }
+}
+
+impl<B: Bitset + Copy + Ord, const N: usize> crate::iso::Refinable for BitsetDigraph<B, N> {
+    type Perm = B::Perm;
+    type Set = B;
+    type Path = seq::SmallSeq<N>;
+    type Coloring = SmallColoring<B, N>;
+
+    #[inline]
+    fn nodes(&self) -> B {
+        Digraph::nodes(self)
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &B::Perm) {
+        Digraph::shuffle(self, permutation)
+    }
+
+    /// Out- and in-degree into `cell` combined into one value
+    #[inline]
+    fn cell_degree(&self, u: usize, cell: &B) -> usize {
+        let out = self.out_neighbours(u).intersection(cell).count();
+        let inc = self.in_neighbours(u).intersection(cell).count();
+        out * (cell.count() + 1) + inc
+    }
+}
+
+impl<B: Bitset + Copy, const N: usize> std::fmt::Debug for BitsetDigraph<B, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.nodes().iter().map(|u| {
+            (u, self.out_neighbours(u).iter().collect::<Vec<_>>())
+        })).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::graph::{Graph16, Graph32};
+
+    fn cycle<D: Digraph>(n: usize) -> D {
+        let mut digraph = D::empty();
+        for i in 0..n {
+            digraph.add_node(i);
+        }
+        for i in 0..n {
+            digraph.add_arc(i, (i + 1) % n);
+        }
+        digraph
+    }
+
+    /// All orientations of the complete graph on `n` nodes
+    fn tournaments<D: Digraph>(n: usize) -> Vec<D> {
+        let pairs: Vec<_> = (0..n).flat_map(|u| (0..u).map(move |v| (u, v))).collect();
+        (0..1u64 << pairs.len()).map(|mask| {
+            let mut digraph = D::empty();
+            for i in 0..n {
+                digraph.add_node(i);
+            }
+            for (k, &(u, v)) in pairs.iter().enumerate() {
+                if mask & (1 << k) != 0 {
+                    digraph.add_arc(u, v);
+                } else {
+                    digraph.add_arc(v, u);
+                }
+            }
+            digraph
+        }).collect()
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        let c: Digraph16 = cycle(5);
+        assert_eq!(c.arcs_count(), 5);
+        for u in 0..5 {
+            assert_eq!(c.out_neighbours(u).iter().collect::<Vec<_>>(), vec![(u + 1) % 5]);
+            assert_eq!(c.in_neighbours(u).iter().collect::<Vec<_>>(), vec![(u + 4) % 5]);
+        }
+        assert!(c.has_arc(0, 1));
+        assert!(!c.has_arc(1, 0));
+        assert!(c.is_oriented());
+        assert!(!c.is_tournament());
+
+        let mut d = c;
+        d.del_node(2);
+        assert_eq!(d.arcs_count(), 3);
+        assert!(d.in_neighbours(3).is_empty());
+    }
+
+    #[test]
+    fn reverse_and_underlying() {
+        let c: Digraph32 = cycle(6);
+        let r = c.reverse();
+        assert!(r.has_arc(1, 0));
+        assert!(!r.has_arc(0, 1));
+        assert_eq!(r.reverse(), c);
+
+        let g: Graph32 = c.underlying();
+        assert_eq!(g.edges_count(), 6);
+        assert!(g.has_edge(5, 0));
+
+        let k4: Digraph16 = Digraph::from_graph(&Graph16::complete(4));
+        assert_eq!(k4, Digraph16::complete(4));
+        assert_eq!(k4.arcs_count(), 12);
+        assert_eq!(k4.underlying::<Graph16>(), Graph16::complete(4));
+    }
+
+    #[test]
+    fn canonical() {
+        use rand::Rng;
+
+        // Same underlying graph but not isomorphic
+        let cyclic: Digraph16 = cycle(3);
+        let mut transitive = Digraph16::empty();
+        for i in 0..3 {
+            transitive.add_node(i);
+        }
+        transitive.add_arc(0, 1);
+        transitive.add_arc(1, 2);
+        transitive.add_arc(0, 2);
+        assert_eq!(cyclic.underlying::<Graph16>(), transitive.underlying::<Graph16>());
+        assert_ne!(cyclic.to_canonical(), transitive.to_canonical());
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut d = Digraph64::empty();
+            for i in 0..20 {
+                d.add_node(i);
+            }
+            for u in 0..20 {
+                for v in 0..20 {
+                    if u != v && rng.gen_bool(0.3) {
+                        d.add_arc(u, v);
+                    }
+                }
+            }
+            let mut perm = <Digraph64 as Digraph>::Perm::new();
+            for i in 0..20 {
+                perm.swap(i, rng.gen_range(0..20));
+            }
+            let mut shuffled = d;
+            Digraph::shuffle(&mut shuffled, &perm);
+            assert_eq!(d.to_canonical(), shuffled.to_canonical());
+            assert_eq!(d.reverse().to_canonical(), shuffled.reverse().to_canonical());
+            assert!(d.to_canonical().is_canonical());
+        }
+    }
+
+    #[test]
+    fn count_tournaments() {
+        // OEIS A000568
+        for (n, count) in [(3, 2), (4, 4), (5, 12)] {
+            let classes: HashSet<Digraph16> = tournaments(n).into_iter()
+                .inspect(|t: &Digraph16| assert!(t.is_tournament()))
+                .map(|t| t.to_canonical())
+                .collect();
+            assert_eq!(classes.len(), count);
+        }
+    }
+}
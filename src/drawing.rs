@@ -0,0 +1,630 @@
+//! Straight-line drawings of embedded graphs. The layouts can be written with
+//! `viz::layouts2svg` and `viz::layout2tikz`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::prelude::*;
+use crate::viz::Layout;
+
+/// Rotations of an embedding with the nodes relabeled to `0..n`, the
+/// neighbours of each node are listed in the order of `RotationSystem::after`.
+struct Rotations {
+    nodes: Vec<usize>,
+    rotations: Vec<Vec<usize>>,
+}
+
+impl Rotations {
+    fn new<G: Graph, R: RotationSystem<G>>(embedding: &R) -> Self {
+        let graph = embedding.to_graph();
+        let nodes: Vec<usize> = graph.nodes().iter().collect();
+        let mut labels = vec![0; G::MAXN];
+        for (i, u) in nodes.iter().enumerate() {
+            labels[*u] = i;
+        }
+
+        let rotations = nodes.iter().map(|u| {
+            let mut rotation = Vec::new();
+            if let Some(v0) = graph.siblings(*u).smallest() {
+                let mut v = v0;
+                loop {
+                    rotation.push(labels[v]);
+                    v = embedding.after(*u, v);
+                    if v == v0 {
+                        break
+                    }
+                }
+            }
+            rotation
+        }).collect();
+
+        Self {
+            nodes,
+            rotations,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn has_edge(&self, u: usize, v: usize) -> bool {
+        self.rotations[u].contains(&v)
+    }
+
+    fn after(&self, u: usize, v: usize) -> usize {
+        let rotation = &self.rotations[u];
+        let i = rotation.iter().position(|w| *w == v).unwrap();
+        rotation[(i + 1) % rotation.len()]
+    }
+
+    /// Inserts the edge `u -> v` directly after `after` in the rotation of `u`.
+    fn insert_after(&mut self, u: usize, after: usize, v: usize) {
+        let rotation = &mut self.rotations[u];
+        let i = rotation.iter().position(|w| *w == after).unwrap();
+        rotation.insert(i + 1, v);
+    }
+
+    /// All faces as the sequence of nodes visited when walking the face.
+    fn faces(&self) -> Vec<Vec<usize>> {
+        let mut used: Vec<Vec<bool>> = self.rotations.iter()
+            .map(|rotation| vec![false; rotation.len()])
+            .collect();
+
+        let mut faces = Vec::new();
+        for u0 in 0..self.len() {
+            for i in 0..self.rotations[u0].len() {
+                if used[u0][i] {
+                    continue
+                }
+                let v0 = self.rotations[u0][i];
+                let mut face = Vec::new();
+                let (mut u, mut v) = (u0, v0);
+                loop {
+                    let j = self.rotations[u].iter().position(|w| *w == v).unwrap();
+                    used[u][j] = true;
+                    face.push(u);
+                    let w = self.after(v, u);
+                    u = v;
+                    v = w;
+                    if u == u0 && v == v0 {
+                        break
+                    }
+                }
+                faces.push(face);
+            }
+        }
+        faces
+    }
+
+    /// Adds edges between the components, each component is placed in an
+    /// arbitrary face of the first one.
+    fn connect(&mut self) {
+        let n = self.len();
+        let mut component = vec![None; n];
+        let mut roots = Vec::new();
+        for u in 0..n {
+            if component[u].is_some() {
+                continue
+            }
+            component[u] = Some(roots.len());
+            let mut stack = vec![u];
+            while let Some(v) = stack.pop() {
+                for w in &self.rotations[v] {
+                    if component[*w].is_none() {
+                        component[*w] = Some(roots.len());
+                        stack.push(*w);
+                    }
+                }
+            }
+            roots.push(u);
+        }
+
+        for root in roots.iter().skip(1) {
+            self.rotations[roots[0]].push(*root);
+            self.rotations[*root].push(roots[0]);
+        }
+    }
+
+    /// Adds edges inside faces until every face is a triangle or has no pair
+    /// of nodes that can be joined without a multiple edge. For a connected
+    /// planar graph with at least three nodes the result is a triangulation.
+    fn triangulate(&mut self) {
+        'outer: loop {
+            for face in self.faces() {
+                let k = face.len();
+                if k <= 3 {
+                    continue
+                }
+                // In a planar face of length at least 4 there is always a pair
+                // of distinct non-adjacent nodes at distance 2 along the face
+                for i in 0..k {
+                    let (t, a, b, c, x) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k], face[(i + 2) % k], face[(i + 3) % k]);
+                    if a != c && !self.has_edge(a, c) {
+                        debug_assert_eq!(self.after(a, t), b);
+                        debug_assert_eq!(self.after(c, b), x);
+                        self.insert_after(a, t, c);
+                        self.insert_after(c, b, a);
+                        continue 'outer
+                    }
+                }
+            }
+            break
+        }
+    }
+
+    /// Canonical ordering of a triangulation. The first two nodes are on the
+    /// outer face and every following node is adjacent to a contiguous part of
+    /// the outer cycle of the nodes before it.
+    fn canonical_ordering(&self) -> Vec<usize> {
+        let n = self.len();
+        let v1 = 0;
+        let v2 = self.rotations[v1][0];
+        let vn = self.after(v2, v1);
+
+        let mut removed = vec![false; n];
+        let mut cycle = vec![v1, vn, v2];
+        let mut order = Vec::new();
+
+        while order.len() < n - 2 {
+            let mut index = vec![None; n];
+            for (i, u) in cycle.iter().enumerate() {
+                index[*u] = Some(i);
+            }
+
+            let i = (1..cycle.len() - 1).find(|i| {
+                self.rotations[cycle[*i]].iter().all(|w| match index[*w] {
+                    Some(j) => j + 1 >= *i && j <= i + 1,
+                    None => true,
+                })
+            }).unwrap();
+
+            let u = cycle[i];
+            removed[u] = true;
+            order.push(u);
+
+            let mut inner = Vec::new();
+            if order.len() < n - 2 {
+                let mut w = self.after(u, cycle[i - 1]);
+                while w != cycle[i + 1] {
+                    debug_assert!(!removed[w]);
+                    inner.push(w);
+                    w = self.after(u, w);
+                }
+            }
+            cycle.splice(i..i + 1, inner);
+        }
+
+        order.push(v2);
+        order.push(v1);
+        order.reverse();
+        order
+    }
+}
+
+/// Crossing-free straight-line drawing of a planar embedding on an integer
+/// grid of size `(2n - 4) x (n - 2)`, using the shift algorithm of
+/// de Fraysseix, Pach and Pollack on a triangulation of the embedding.
+/// Only the nodes of the embedding are placed. Returns `None` if the
+/// embedding is not planar.
+pub fn planar_grid<G: Graph, R: RotationSystem<G>>(embedding: &R) -> Option<Layout> {
+    if embedding.genus() != 0 {
+        return None
+    }
+
+    let mut rotations = Rotations::new(embedding);
+    let n = rotations.len();
+    let mut positions = vec![None; G::MAXN];
+    if n < 3 {
+        for (i, u) in rotations.nodes.iter().enumerate() {
+            positions[*u] = Some((2.0 * i as f64, 0.0));
+        }
+        return Some(Layout { positions })
+    }
+
+    rotations.connect();
+    rotations.triangulate();
+    let order = rotations.canonical_ordering();
+
+    let mut x = vec![0i64; n];
+    let mut y = vec![0i64; n];
+    // The nodes that are shifted together with each node on the contour
+    let mut shifted: Vec<Vec<usize>> = vec![Vec::new(); n];
+
+    let (v1, v2, v3) = (order[0], order[1], order[2]);
+    x[v2] = 2;
+    x[v3] = 1;
+    y[v3] = 1;
+    for u in &[v1, v2, v3] {
+        shifted[*u].push(*u);
+    }
+    let mut contour = vec![v1, v3, v2];
+
+    for u in order.iter().skip(3) {
+        let u = *u;
+        let mut contour_neighbours = contour.iter().enumerate()
+            .filter(|(_, w)| rotations.has_edge(u, **w))
+            .map(|(i, _)| i);
+        let p = contour_neighbours.next().unwrap();
+        let q = contour_neighbours.next_back().unwrap();
+
+        for (i, w) in contour.iter().enumerate().skip(p + 1) {
+            let dx = if i < q { 1 } else { 2 };
+            for v in &shifted[*w] {
+                x[*v] += dx;
+            }
+        }
+
+        let (wp, wq) = (contour[p], contour[q]);
+        x[u] = (x[wp] - y[wp] + x[wq] + y[wq]) / 2;
+        y[u] = (x[wq] - x[wp] + y[wp] + y[wq]) / 2;
+
+        let mut u_shifted = vec![u];
+        for w in &contour[p + 1..q] {
+            u_shifted.extend_from_slice(&shifted[*w]);
+        }
+        shifted[u] = u_shifted;
+
+        contour.splice(p + 1..q, std::iter::once(u));
+    }
+
+    for (i, u) in rotations.nodes.iter().enumerate() {
+        positions[*u] = Some((x[i] as f64, y[i] as f64));
+    }
+    Some(Layout { positions })
+}
+
+/// Drawing of a graph on the torus, seen as the unit square with opposite
+/// sides identified.
+#[derive(Clone, Debug)]
+pub struct TorusLayout {
+    /// Node positions in the unit square
+    pub layout: Layout,
+    /// Each edge `(u, v, (dx, dy))` is drawn as a straight line from `u` to the
+    /// copy of `v` translated by `(dx, dy)` and wraps around the square
+    /// boundary when the offset is not zero.
+    pub edges: Vec<(usize, usize, (i64, i64))>,
+}
+
+/// Solves `a x = b` for the columns of `b` by gaussian elimination.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    let n = a.len();
+    for i in 0..n {
+        let pivot = (i..n).max_by(|j, k| a[*j][i].abs().partial_cmp(&a[*k][i].abs()).unwrap()).unwrap();
+        a.swap(i, pivot);
+        b.swap(i, pivot);
+        let row = a[i].clone();
+        for j in i + 1..n {
+            let f = a[j][i] / row[i];
+            if f == 0.0 {
+                continue
+            }
+            for (x, y) in a[j].iter_mut().zip(&row).skip(i) {
+                *x -= f * y;
+            }
+            b[j][0] -= f * b[i][0];
+            b[j][1] -= f * b[i][1];
+        }
+    }
+
+    let mut x = vec![[0.0; 2]; n];
+    for i in (0..n).rev() {
+        for c in 0..2 {
+            let sum: f64 = (i + 1..n).map(|k| a[i][k] * x[k][c]).sum();
+            x[i][c] = (b[i][c] - sum) / a[i][i];
+        }
+    }
+    x
+}
+
+/// Draws a connected genus 1 embedding on the torus. The graph is first
+/// triangulated as far as possible and then drawn with a periodic Tutte
+/// embedding, where every node is at the barycenter of its neighbours. The
+/// drawing is crossing-free for triangulations and other 3-connected graphs.
+/// Returns `None` if the embedding is not connected or does not have genus 1.
+pub fn torus_layout<G: Graph, R: RotationSystem<G>>(embedding: &R) -> Option<TorusLayout> {
+    if embedding.genus() != 1 || !embedding.to_graph().is_connected() {
+        return None
+    }
+
+    let original = Rotations::new(embedding);
+    let mut rotations = Rotations::new(embedding);
+    rotations.triangulate();
+    let n = rotations.len();
+
+    let faces = rotations.faces();
+    let mut dart_face = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (i, u) in face.iter().enumerate() {
+            dart_face.insert((*u, face[(i + 1) % face.len()]), f);
+        }
+    }
+
+    // Tree-cotree decomposition: a spanning tree, a spanning tree of the dual
+    // on the remaining edges and two edges left over that generate the
+    // homology of the torus.
+    let mut tree = HashSet::new();
+    let mut visited = vec![false; n];
+    let mut queue = VecDeque::from(vec![0]);
+    visited[0] = true;
+    while let Some(u) = queue.pop_front() {
+        for v in &rotations.rotations[u] {
+            if !visited[*v] {
+                visited[*v] = true;
+                tree.insert((u.min(*v), u.max(*v)));
+                queue.push_back(*v);
+            }
+        }
+    }
+
+    let mut cotree = HashSet::new();
+    let mut face_order = vec![0];
+    let mut parent = vec![None; faces.len()];
+    let mut visited = vec![false; faces.len()];
+    visited[0] = true;
+    let mut i = 0;
+    while i < face_order.len() {
+        let f = face_order[i];
+        let face = &faces[f];
+        for (j, u) in face.iter().enumerate() {
+            let v = face[(j + 1) % face.len()];
+            let g = dart_face[&(v, *u)];
+            let e = (*u.min(&v), *u.max(&v));
+            if !visited[g] && !tree.contains(&e) {
+                visited[g] = true;
+                parent[g] = Some((v, *u));
+                cotree.insert(e);
+                face_order.push(g);
+            }
+        }
+        i += 1;
+    }
+
+    // Offsets of each dart in the lattice, chosen such that the offsets sum
+    // to zero around every face
+    let mut offsets: HashMap<(usize, usize), (i64, i64)> = HashMap::new();
+    let mut generators = vec![(1, 0), (0, 1)].into_iter();
+    for u in 0..n {
+        for v in &rotations.rotations[u] {
+            let e = (u.min(*v), u.max(*v));
+            let offset = if tree.contains(&e) || cotree.contains(&e) {
+                (0, 0)
+            } else if u < *v {
+                generators.next()?
+            } else {
+                continue
+            };
+            offsets.insert((u, *v), offset);
+            offsets.insert((*v, u), (-offset.0, -offset.1));
+        }
+    }
+    for f in face_order.iter().skip(1).rev() {
+        let (u, v) = parent[*f].unwrap();
+        let face = &faces[*f];
+        let (mut dx, mut dy) = (0, 0);
+        for (j, a) in face.iter().enumerate() {
+            let b = face[(j + 1) % face.len()];
+            if (*a, b) != (u, v) {
+                let offset = offsets[&(*a, b)];
+                dx -= offset.0;
+                dy -= offset.1;
+            }
+        }
+        offsets.insert((u, v), (dx, dy));
+        offsets.insert((v, u), (-dx, -dy));
+    }
+
+    // Periodic Tutte embedding with node 0 fixed at the origin
+    let mut a = vec![vec![0.0; n - 1]; n - 1];
+    let mut b = vec![[0.0; 2]; n - 1];
+    for u in 1..n {
+        for v in &rotations.rotations[u] {
+            let offset = offsets[&(u, *v)];
+            a[u - 1][u - 1] += 1.0;
+            if *v != 0 {
+                a[u - 1][*v - 1] -= 1.0;
+            }
+            b[u - 1][0] += offset.0 as f64;
+            b[u - 1][1] += offset.1 as f64;
+        }
+    }
+    let mut pos = vec![[0.0; 2]];
+    pos.extend(solve(a, b));
+
+    let mut positions = vec![None; G::MAXN];
+    let cell: Vec<(i64, i64)> = pos.iter()
+        .map(|p| (p[0].floor() as i64, p[1].floor() as i64))
+        .collect();
+    for (i, u) in original.nodes.iter().enumerate() {
+        positions[*u] = Some((pos[i][0] - cell[i].0 as f64, pos[i][1] - cell[i].1 as f64));
+    }
+
+    let mut edges = Vec::new();
+    for u in 0..n {
+        for v in &original.rotations[u] {
+            if u < *v {
+                let offset = offsets[&(u, *v)];
+                edges.push((original.nodes[u], original.nodes[*v], (
+                    offset.0 + cell[*v].0 - cell[u].0,
+                    offset.1 + cell[*v].1 - cell[u].1,
+                )));
+            }
+        }
+    }
+
+    Some(TorusLayout {
+        layout: Layout { positions },
+        edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph64};
+    use crate::{planar, toroidal};
+
+    fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    }
+
+    fn check_drawing<G: Graph>(graph: &G, layout: &Layout) {
+        let n = graph.nodes().count();
+        let nodes: Vec<usize> = graph.nodes().iter().collect();
+        for u in &nodes {
+            let (x, y) = layout.get(*u);
+            assert!(x >= 0.0 && y >= 0.0);
+            assert!(x <= (2 * n) as f64 && y <= n as f64);
+            for v in &nodes {
+                assert!(u == v || layout.get(*u) != layout.get(*v));
+            }
+        }
+
+        let edges: Vec<_> = graph.edges().collect();
+        for (u, v) in &edges {
+            let (a, b) = (layout.get(*u), layout.get(*v));
+            // No node lies on an edge it is not part of
+            for w in &nodes {
+                let c = layout.get(*w);
+                if w != u && w != v && cross(a, b, c) == 0.0 {
+                    assert!(c.0 < a.0.min(b.0) || c.0 > a.0.max(b.0)
+                         || c.1 < a.1.min(b.1) || c.1 > a.1.max(b.1));
+                }
+            }
+            // No two edges without common endpoints cross
+            for (s, t) in &edges {
+                if [u, v].contains(&s) || [u, v].contains(&t) {
+                    continue
+                }
+                let (c, d) = (layout.get(*s), layout.get(*t));
+                assert!(!(cross(a, b, c) * cross(a, b, d) < 0.0 && cross(c, d, a) * cross(c, d, b) < 0.0));
+            }
+        }
+    }
+
+    /// Random stacked triangulation with some of the edges removed
+    fn random_planar(n: usize) -> Graph64 {
+        use rand::prelude::*;
+        let mut rng = rand::thread_rng();
+
+        let mut graph = Graph64::complete(3);
+        let mut faces = vec![(0, 1, 2), (0, 2, 1)];
+        for u in 3..n {
+            let (a, b, c) = faces.swap_remove(rng.gen_range(0..faces.len()));
+            graph.add_node(u);
+            graph.add_edge(u, a);
+            graph.add_edge(u, b);
+            graph.add_edge(u, c);
+            faces.extend_from_slice(&[(a, b, u), (b, c, u), (c, a, u)]);
+        }
+
+        let edges: Vec<_> = graph.edges().collect();
+        for (u, v) in edges {
+            if rng.gen_bool(0.3) {
+                graph.del_edge(u, v);
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn planar_grid_random() {
+        for n in 3..=40 {
+            let graph = random_planar(n);
+            let embedding = planar::find_embedding(&graph).unwrap();
+            let layout = planar_grid(&embedding).unwrap();
+            check_drawing(&embedding.to_graph(), &layout);
+        }
+    }
+
+    #[test]
+    fn planar_grid_special() {
+        let mut graph = Graph64::empty();
+        for u in 0..40 {
+            graph.add_node(u);
+        }
+        // A grid graph
+        for u in 0..40 {
+            if u % 8 != 7 {
+                graph.add_edge(u, u + 1);
+            }
+            if u + 8 < 40 {
+                graph.add_edge(u, u + 8);
+            }
+        }
+        let layout = planar_grid(&planar::find_embedding(&graph).unwrap()).unwrap();
+        check_drawing(&graph, &layout);
+
+        // Trees, isolated nodes and tiny graphs
+        let mut graph = Graph16::empty();
+        for u in 0..8 {
+            graph.add_node(u);
+        }
+        for u in 1..5 {
+            graph.add_edge(0, u);
+        }
+        let layout = planar_grid(&planar::find_embedding(&graph).unwrap()).unwrap();
+        check_drawing(&graph, &layout);
+
+        for n in 0..4 {
+            let graph = Graph16::complete(n);
+            let layout = planar_grid(&planar::find_embedding(&graph).unwrap()).unwrap();
+            check_drawing(&graph, &layout);
+        }
+
+        let k5 = crate::toroidal::find_embedding(&Graph16::complete(5)).unwrap();
+        assert!(planar_grid(&k5).is_none());
+    }
+
+    fn check_torus_drawing(torus: &TorusLayout) {
+        let nodes: Vec<_> = torus.layout.nodes().collect();
+        for (u, (x, y)) in &nodes {
+            assert!((0.0..1.0).contains(x) && (0.0..1.0).contains(y));
+            for (v, p) in &nodes {
+                assert!(u == v || (x - p.0).abs() + (y - p.1).abs() > 1e-6);
+            }
+        }
+
+        let segments: Vec<_> = torus.edges.iter().map(|(u, v, (dx, dy))| {
+            let (xv, yv) = torus.layout.get(*v);
+            (torus.layout.get(*u), (xv + *dx as f64, yv + *dy as f64))
+        }).collect();
+        let eps = 1e-9;
+        for (i, (a, b)) in segments.iter().enumerate() {
+            for (c, d) in segments.iter().skip(i + 1) {
+                for tx in -2..=2 {
+                    for ty in -2..=2 {
+                        let c = (c.0 + tx as f64, c.1 + ty as f64);
+                        let d = (d.0 + tx as f64, d.1 + ty as f64);
+                        assert!(!(cross(*a, *b, c) * cross(*a, *b, d) < -eps
+                                  && cross(c, d, *a) * cross(c, d, *b) < -eps));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn torus_layout_complete() {
+        for n in 5..=7 {
+            let graph = Graph16::complete(n);
+            let embedding = toroidal::find_embedding(&graph).unwrap();
+            let torus = torus_layout(&embedding).unwrap();
+            assert_eq!(torus.edges.len(), n * (n - 1) / 2);
+            check_torus_drawing(&torus);
+        }
+
+        let mut k44 = Graph16::empty();
+        for u in 0..8 {
+            k44.add_node(u);
+        }
+        for u in 0..4 {
+            for v in 4..8 {
+                k44.add_edge(u, v);
+            }
+        }
+        let torus = torus_layout(&toroidal::find_embedding(&k44).unwrap()).unwrap();
+        check_torus_drawing(&torus);
+
+        let k4 = planar::fastdmp(&Graph16::complete(4)).unwrap();
+        assert!(torus_layout(&k4).is_none());
+    }
+}
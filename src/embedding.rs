@@ -1,10 +1,12 @@
-use crate::seq::{Seq, SmallSeq, SeqPermutations};
-use crate::bitset::{self, Intset, Bitset};
+use std::collections::HashMap;
+use crate::seq::{Seq, SmallSeq, SeqPermutations, DynSeq, DynSeqPermutations};
+use crate::bitset::{self, Intset, Bitset, DynBitset};
 use crate::graph::{self, Graph};
 
 pub type RotationSystem16 = SmallRotationSystem<bitset::Bitset16, graph::Graph16, 16>;
 pub type RotationSystem32 = SmallRotationSystem<bitset::Bitset32, graph::Graph32, 32>;
 pub type RotationSystem64 = SmallRotationSystem<bitset::Bitset64, graph::Graph64, 64>;
+pub type RotationSystem128 = SmallRotationSystem<bitset::Bitset128, graph::Graph128, 128>;
 
 #[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
 pub struct Face {
@@ -136,6 +138,49 @@ pub trait RotationSystem<G: Graph>: Sized + Clone {
     fn embed_disconnected(&mut self, other: &Self);
 }
 
+/// Builds an embedding from `(node, rotation)` pairs where each rotation lists
+/// the neighbours in the order of `RotationSystem::after`. On error the index
+/// of the first invalid pair is returned.
+pub(crate) fn from_rotations<G: Graph>(rotations: &[(usize, Vec<usize>)]) -> Result<G::Embedding, usize> {
+    let mut graph = G::empty();
+    for (i, (u, _)) in rotations.iter().enumerate() {
+        if *u >= G::MAXN || graph.has_node(*u) {
+            return Err(i)
+        }
+        graph.add_node(*u);
+    }
+    for (i, (u, rotation)) in rotations.iter().enumerate() {
+        let mut listed = G::Set::new();
+        for v in rotation {
+            if *v >= G::MAXN || !graph.has_node(*v) || v == u || listed.get(*v) {
+                return Err(i)
+            }
+            listed.set(*v);
+            graph.add_edge(*u, *v);
+        }
+    }
+    // Without duplicates a rotation covers all siblings only if every node
+    // listing `u` is also listed by `u`
+    for (i, (u, rotation)) in rotations.iter().enumerate() {
+        if rotation.len() != graph.siblings(*u).count() {
+            return Err(i)
+        }
+    }
+
+    let mut embedding = G::Embedding::simple(&graph);
+    for (u, rotation) in rotations {
+        // Keep the first edge so that the node is never removed
+        for v in rotation.iter().skip(1) {
+            embedding.remove_edge_dir(*u, *v);
+        }
+        for (v, w) in rotation.iter().zip(rotation.iter().skip(1)) {
+            embedding.insert_edge(*u, *v, *w);
+        }
+    }
+
+    Ok(embedding)
+}
+
 pub struct FaceIter<'a, G: Graph, R: RotationSystem<G>> {
     embedding: &'a R,
     face: Face,
@@ -470,6 +515,301 @@ impl<B: Bitset, G: Clone, const N: usize> Iterator for SmallRotationSystemEnumer
     }
 }
 
+/// Rotation system for graphs with `Set = DynBitset`, the rotations are
+/// stored in hash maps instead of `N x N` arrays.
+#[derive(Clone)]
+pub struct DynRotationSystem<G> {
+    nodes: DynBitset,
+    edges: Vec<DynBitset>,
+    order: Vec<HashMap<usize, usize>>,
+    order_inv: Vec<HashMap<usize, usize>>,
+    _marker: std::marker::PhantomData<G>,
+}
+
+impl<G> DynRotationSystem<G> {
+    pub fn siblings<'a>(&'a self, u: usize) -> impl 'a + Iterator<Item = usize> {
+        let mut v = self.edges.get(u).and_then(|e| e.smallest());
+        let v0 = v;
+
+        std::iter::from_fn(move || {
+            if let Some(w) = v {
+                v = Some(self.order[u][&w]);
+                if v == v0 {
+                    v = None
+                }
+                Some(w)
+            } else {
+                None
+            }
+        })
+    }
+
+    #[inline]
+    fn grow(&mut self, u: usize) {
+        if self.edges.len() <= u {
+            self.edges.resize(u + 1, DynBitset::new());
+            self.order.resize(u + 1, HashMap::new());
+            self.order_inv.resize(u + 1, HashMap::new());
+        }
+    }
+
+    #[inline]
+    fn edges(&self, u: usize) -> DynBitset {
+        self.edges.get(u).cloned().unwrap_or_default()
+    }
+}
+
+impl<G: Graph<Set = DynBitset>> RotationSystem<G> for DynRotationSystem<G> {
+    type EnumIter = DynRotationSystemEnumerate<G>;
+    type FacesIter = DynFacesIter;
+
+    fn empty() -> Self {
+        Self {
+            nodes: DynBitset::new(),
+            edges: Vec::new(),
+            order: Vec::new(),
+            order_inv: Vec::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn simple(graph: &G) -> Self {
+        let mut res = Self::empty();
+        res.nodes = graph.nodes();
+
+        for u in graph.nodes().iter() {
+            res.grow(u);
+            res.edges[u] = graph.siblings(u);
+            for (v, w) in graph.siblings(u).iter()
+                .zip(graph.siblings(u).iter().cycle().skip(1))
+            {
+                res.order[u].insert(v, w);
+                res.order_inv[u].insert(w, v);
+            }
+        }
+
+        res
+    }
+
+    fn enumerate(graph: &G) -> DynRotationSystemEnumerate<G> {
+        let curr = Self::simple(graph);
+        let permutations = vec![DynSeqPermutations::empty(); curr.edges.len()];
+
+        let flip_node = curr.nodes.iter()
+            .find(|i| curr.edges[*i].count() > 2);
+
+        let mut enumerate = DynRotationSystemEnumerate {
+            flip_node,
+            curr,
+            permutations
+        };
+
+        if let Some(i) = enumerate.curr.nodes.smallest() {
+            enumerate.new_perm(i);
+        }
+
+        enumerate
+    }
+
+    fn to_graph(&self) -> G {
+        let mut graph = G::empty();
+        for u in self.nodes.iter() {
+            graph.add_node(u);
+        }
+        for u in self.nodes.iter() {
+            for v in self.edges[u].iter() {
+                graph.add_edge(u, v);
+            }
+        }
+        graph
+    }
+
+    fn genus(&self) -> usize {
+        let graph: G = self.to_graph();
+        let edge_count = graph.edges_count();
+        let component_count = graph.components().count();
+        let face_count = std::cmp::max(1, self.faces().count());
+        (3 + edge_count + component_count - 1 - self.nodes.count() - face_count) / 2
+    }
+
+    fn faces<'a>(&'a self) -> Faces<'a, G, Self> {
+        Faces {
+            embedding: self,
+            iter: DynFacesIter {
+                used: vec![DynBitset::new(); self.edges.len()],
+                visited: DynBitset::new(),
+            },
+        }
+    }
+
+    #[inline]
+    fn after(&self, u: usize, v: usize) -> usize {
+        self.order.get(u).and_then(|order| order.get(&v)).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    fn before(&self, u: usize, v: usize) -> usize {
+        self.order_inv.get(u).and_then(|order| order.get(&v)).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    fn remove_node(&mut self, u: usize) {
+        for v in self.edges(u).iter() {
+            self.remove_edge(u, v);
+        }
+        self.nodes.clear(u);
+    }
+
+    #[inline]
+    fn insert_edge(&mut self, node: usize, after: usize, dest: usize) {
+        self.grow(node);
+        self.nodes.set(dest);
+        self.edges[node].set(dest);
+        let k = self.after(node, after);
+        self.order[node].insert(dest, k);
+        self.order_inv[node].insert(dest, after);
+        self.order_inv[node].insert(k, dest);
+        self.order[node].insert(after, dest);
+    }
+
+    #[inline]
+    fn insert_edge_any(&mut self, node: usize, dest: usize) {
+        if let Some(i) = self.edges(node).smallest() {
+            self.insert_edge(node, i, dest);
+        } else {
+            self.insert_edge(node, dest, dest);
+        }
+    }
+
+    #[inline]
+    fn remove_edge_dir(&mut self, u: usize, v: usize) {
+        self.grow(u);
+        self.edges[u].clear(v);
+        if self.edges[u].is_empty() {
+            self.nodes.clear(u);
+        }
+        let before = self.before(u, v);
+        let after  = self.after(u, v);
+        self.order[u].insert(before, after);
+        self.order_inv[u].insert(after, before);
+    }
+
+    fn embed_disconnected(&mut self, other: &Self) {
+        debug_assert!(self.nodes.intersection(&other.nodes).is_empty());
+        self.nodes = self.nodes.union(&other.nodes);
+        if let Some(u) = other.edges.len().checked_sub(1) {
+            self.grow(u);
+        }
+        for (i, edges) in other.edges.iter().enumerate() {
+            self.edges[i] = self.edges[i].union(edges);
+        }
+        for u in other.nodes.iter() {
+            self.order[u] = other.order[u].clone();
+            self.order_inv[u] = other.order_inv[u].clone();
+        }
+    }
+}
+
+impl<G> std::fmt::Debug for DynRotationSystem<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.nodes.iter().map(|u| {
+            (u, self.siblings(u).collect::<Vec<_>>())
+        })).finish()
+    }
+}
+
+pub struct DynFacesIter {
+    used: Vec<DynBitset>,
+    visited: DynBitset,
+}
+
+impl<G: Graph<Set = DynBitset>> FacesIter<G, DynRotationSystem<G>> for DynFacesIter {
+    #[inline]
+    fn next_face(&mut self, embedding: &DynRotationSystem<G>) -> Option<Face> {
+        while let Some(u) = embedding.nodes.difference(&self.visited).smallest() {
+            if let Some(v) = embedding.edges[u]
+                .difference(&self.used[u])
+                .smallest()
+            {
+                let face = Face { u0: u, v0: v };
+                for (ki, kj) in embedding.face(face) {
+                    self.used[ki].set(kj);
+                }
+                return Some(face)
+            }
+            self.visited.set(u);
+        }
+        None
+    }
+}
+
+pub struct DynRotationSystemEnumerate<G> {
+    flip_node: Option<usize>,
+    curr: DynRotationSystem<G>,
+    permutations: Vec<DynSeqPermutations>,
+}
+
+impl<G> DynRotationSystemEnumerate<G> {
+    #[inline]
+    fn new_perm(&mut self, i: usize) {
+        let mut seq = DynSeq::new();
+        for j in self.curr.edges[i].iter().skip(1) {
+            seq.push(j);
+        }
+        self.permutations[i] = seq.permutations();
+    }
+
+    #[inline]
+    fn next_perm(&mut self, i: usize) -> bool {
+        if let Some(new) = self.permutations[i].next() {
+            if let Some(j0) = self.curr.edges[i].smallest() {
+                let mut last = j0;
+                for next in new.iter() {
+                    self.curr.order[i].insert(last, next);
+                    self.curr.order_inv[i].insert(next, last);
+                    last = next;
+                }
+                self.curr.order[i].insert(last, j0);
+                self.curr.order_inv[i].insert(j0, last);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    fn flip_perm(&self) -> bool {
+        if let Some(i) = self.flip_node {
+            let j = self.curr.edges[i].smallest().unwrap();
+            self.curr.order[i][&j] < self.curr.order_inv[i][&j]
+        } else {
+            true
+        }
+    }
+}
+
+impl<G: Clone> Iterator for DynRotationSystemEnumerate<G> {
+    type Item = DynRotationSystem<G>;
+
+    #[inline]
+    fn next(&mut self) -> Option<DynRotationSystem<G>> {
+        for i in self.curr.nodes.iter().rev() {
+            while self.next_perm(i) {
+                for j in self.curr.nodes.intersection(&DynBitset::mask_ge(i+1)).iter() {
+                    self.new_perm(j);
+                    self.next_perm(j);
+                }
+                if self.flip_perm() {
+                    return Some(self.curr.clone())
+                }
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -550,15 +890,7 @@ mod tests {
 
     #[test]
     fn count_toroidal_embeddings_k33() {
-        let mut k33 = Graph16::empty();
-        for i in 0..6 {
-            k33.add_node(i);
-        }
-        for i in 0..3 {
-            for j in 3..6 {
-                k33.add_edge(i, j);
-            }
-        }
+        let k33: Graph16 = crate::families::complete_bipartite(3, 3).unwrap();
 
         let count = RotationSystem16::enumerate(&k33)
             .filter(|embedding| embedding.genus() == 1)
@@ -567,4 +899,15 @@ mod tests {
         // @myrvold2018large
         assert_eq!(count, 20);
     }
+
+    #[test]
+    fn count_toroidal_embeddings_k5_dyn() {
+        let k5 = graph::DynGraph::complete(5);
+
+        let count = DynRotationSystem::enumerate(&k5)
+            .filter(|embedding| embedding.genus() == 1)
+            .count();
+
+        assert_eq!(count, 231);
+    }
 }
@@ -1,7 +1,6 @@
 use std::collections::HashSet;
 use crate::graph::{Graph, Graph16};
 use crate::permutation::{Permutation, Perm16};
-use crate::seq::{Seq, Seq16};
 use crate::bitset::{Intset, Bitset, Bitset16};
 use crate::iso::search_tree;
 
@@ -31,41 +30,6 @@ fn check_auto<'a>(n: usize, cut_vert: usize, auto_gens: &'a HashSet<Perm16>) ->
     })
 }
 
-fn compute_orbits(n: usize, auto_gens: &HashSet<Perm16>) -> Seq16 {
-    let mut orbits = Seq16::new();
-    for i in 0..n {
-        orbits.push(i);
-    }
-
-    for perm in auto_gens {
-        for i in 0..n {
-            if perm.get(i) != i {
-                let mut j1 = usize::from(orbits[i]);
-                while usize::from(orbits[j1]) != j1 {
-                    j1 = usize::from(orbits[j1]);
-                }
-
-                let mut j2 = usize::from(orbits[perm.get(i)]);
-                while usize::from(orbits[j2]) != j2 {
-                    j2 = usize::from(orbits[j2]);
-                }
-
-                if j1 < j2 {
-                    orbits[j2] = j1 as u8;
-                } else {
-                    orbits[j1] = j2 as u8;
-                }
-            }
-        }
-
-        for i in 0..n {
-            orbits[i] = orbits[usize::from(orbits[i])];
-        }
-    }
-
-    orbits
-}
-
 fn extension_cardinality_bounds(n: usize, ne: usize, dmax: usize) -> (usize, usize) {
     let lower_bound = if n == 1 {
         0
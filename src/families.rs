@@ -0,0 +1,256 @@
+//! Constructors for well known graphs and graph families, on nodes `0..n`.
+//! They fail with `TooManyNodes` when the graph does not fit into `G`.
+
+use crate::prelude::*;
+use crate::operations::{with_nodes, TooManyNodes};
+
+/// The number of nodes of a family member, `None` when it overflowed
+fn node_count<G: Graph>(n: Option<usize>) -> Result<usize, TooManyNodes> {
+    n.ok_or(TooManyNodes { n: usize::MAX, maxn: G::MAXN })
+}
+
+/// The path on `n` nodes
+pub fn path<G: Graph>(n: usize) -> Result<G, TooManyNodes> {
+    let mut graph = with_nodes::<G>(n)?;
+    for u in 1..n {
+        graph.add_edge(u - 1, u);
+    }
+    Ok(graph)
+}
+
+/// The cycle on `n` nodes, for `n < 3` this is the path
+pub fn cycle<G: Graph>(n: usize) -> Result<G, TooManyNodes> {
+    let mut graph = path::<G>(n)?;
+    if n >= 3 {
+        graph.add_edge(n - 1, 0);
+    }
+    Ok(graph)
+}
+
+/// `K_{1,n}` with centre `0`
+pub fn star<G: Graph>(n: usize) -> Result<G, TooManyNodes> {
+    complete_bipartite(1, n)
+}
+
+/// A cycle on the nodes `1..=n` and the hub `0` adjacent to all of them
+pub fn wheel<G: Graph>(n: usize) -> Result<G, TooManyNodes> {
+    let mut graph = star::<G>(n)?;
+    for u in 2..=n {
+        graph.add_edge(u - 1, u);
+    }
+    if n >= 3 {
+        graph.add_edge(n, 1);
+    }
+    Ok(graph)
+}
+
+/// `K_{m,n}` with the sides `0..m` and `m..m + n`
+pub fn complete_bipartite<G: Graph>(m: usize, n: usize) -> Result<G, TooManyNodes> {
+    let mut graph = with_nodes::<G>(node_count::<G>(m.checked_add(n))?)?;
+    for u in 0..m {
+        for v in m..m + n {
+            graph.add_edge(u, v);
+        }
+    }
+    Ok(graph)
+}
+
+/// The `d`-dimensional cube, nodes are adjacent when their labels differ in
+/// one bit
+pub fn hypercube<G: Graph>(d: usize) -> Result<G, TooManyNodes> {
+    let n = if d < usize::BITS as usize { 1 << d } else { usize::MAX };
+    let mut graph = with_nodes::<G>(n)?;
+    for u in 0..1 << d {
+        for i in 0..d {
+            graph.add_edge(u, u ^ (1 << i));
+        }
+    }
+    Ok(graph)
+}
+
+/// The `rows` by `cols` grid, node `r * cols + c` is in row `r` and column `c`
+pub fn grid<G: Graph>(rows: usize, cols: usize) -> Result<G, TooManyNodes> {
+    let mut graph = with_nodes::<G>(node_count::<G>(rows.checked_mul(cols))?)?;
+    for r in 0..rows {
+        for c in 0..cols {
+            if r + 1 < rows {
+                graph.add_edge(r * cols + c, (r + 1) * cols + c);
+            }
+            if c + 1 < cols {
+                graph.add_edge(r * cols + c, r * cols + c + 1);
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// The cycle on `2n` nodes with opposite nodes joined
+pub fn mobius_ladder<G: Graph>(n: usize) -> Result<G, TooManyNodes> {
+    circulant(node_count::<G>(n.checked_mul(2))?, &[1, n])
+}
+
+/// The outer cycle `0..n`, the spokes `u, u + n` and the inner star polygon
+/// joining `n + u` to `n + (u + k) % n`
+pub fn generalized_petersen<G: Graph>(n: usize, k: usize) -> Result<G, TooManyNodes> {
+    let mut graph = with_nodes::<G>(node_count::<G>(n.checked_mul(2))?)?;
+    for u in 0..n {
+        let (next, inner) = ((u + 1) % n, n + (u + k) % n);
+        if next != u {
+            graph.add_edge(u, next);
+        }
+        if inner != n + u {
+            graph.add_edge(n + u, inner);
+        }
+        graph.add_edge(u, n + u);
+    }
+    Ok(graph)
+}
+
+pub fn petersen<G: Graph>() -> Result<G, TooManyNodes> {
+    generalized_petersen(5, 2)
+}
+
+/// Nodes `0..n` where `u` is adjacent to `u ± j` modulo `n` for every jump `j`
+pub fn circulant<G: Graph>(n: usize, jumps: &[usize]) -> Result<G, TooManyNodes> {
+    let mut graph = with_nodes::<G>(n)?;
+    for u in 0..n {
+        for &j in jumps {
+            let v = (u + j) % n;
+            if v != u {
+                graph.add_edge(u, v);
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// Replaces the triangle `a, b, c` by a new node adjacent to its corners
+fn delta_to_wye<G: Graph>(graph: &G, a: usize, b: usize, c: usize) -> Result<G, TooManyNodes> {
+    let w = (0..G::MAXN).find(|&w| !graph.has_node(w)).ok_or(TooManyNodes { n: G::MAXN + 1, maxn: G::MAXN })?;
+    let mut graph = graph.clone();
+    graph.del_edge(a, b);
+    graph.del_edge(b, c);
+    graph.del_edge(c, a);
+    graph.add_node(w);
+    for &u in &[a, b, c] {
+        graph.add_edge(w, u);
+    }
+    Ok(graph)
+}
+
+/// Replaces the node `w` of degree three by a triangle on its neighbours
+fn wye_to_delta<G: Graph>(graph: &G, w: usize) -> G {
+    let corners: Vec<_> = graph.siblings(w).iter().collect();
+    let mut graph = graph.clone();
+    graph.del_node(w);
+    graph.add_edge(corners[0], corners[1]);
+    graph.add_edge(corners[1], corners[2]);
+    graph.add_edge(corners[2], corners[0]);
+    graph
+}
+
+/// The seven graphs obtained from `K6` by Δ-Y and Y-Δ transformations, the
+/// forbidden minors for linkless embeddings. They are canonical and sorted by
+/// their number of nodes, with `K6` first and the Petersen graph last.
+pub fn petersen_family<G: Graph + Ord>() -> Result<Vec<G>, TooManyNodes> {
+    // The Petersen graph is the largest member
+    if G::MAXN < 10 {
+        return Err(TooManyNodes { n: 10, maxn: G::MAXN })
+    }
+    let mut family = std::collections::BTreeSet::new();
+    let mut todo = vec![G::complete(6).to_canonical()];
+    while let Some(graph) = todo.pop() {
+        if !family.insert(graph.clone()) {
+            continue
+        }
+        let nodes: Vec<_> = graph.nodes().iter().collect();
+        for (i, &a) in nodes.iter().enumerate() {
+            for (j, &b) in nodes.iter().enumerate().skip(i + 1) {
+                for &c in &nodes[j + 1..] {
+                    if graph.has_edge(a, b) && graph.has_edge(b, c) && graph.has_edge(c, a) {
+                        todo.push(delta_to_wye(&graph, a, b, c)?.to_canonical());
+                    }
+                }
+            }
+        }
+        for &w in &nodes {
+            let corners: Vec<_> = graph.siblings(w).iter().collect();
+            let independent = corners.iter().all(|&u| corners.iter().all(|&v| u == v || !graph.has_edge(u, v)));
+            if corners.len() == 3 && independent {
+                todo.push(wye_to_delta(&graph, w).to_canonical());
+            }
+        }
+    }
+
+    let mut family: Vec<_> = family.into_iter().collect();
+    family.sort_by_key(|graph| graph.nodes().count());
+    Ok(family)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32, Graph64};
+    use crate::invariants::{degree_sequence, girth};
+
+    #[test]
+    fn small_families() {
+        let graph: Graph16 = cycle(5).unwrap();
+        assert_eq!(degree_sequence(&graph), vec![2; 5]);
+        assert_eq!(path::<Graph16>(4).unwrap().edges_count(), 3);
+        assert_eq!(star::<Graph16>(4).unwrap().siblings(0).count(), 4);
+
+        let wheel: Graph16 = wheel(5).unwrap();
+        assert_eq!(wheel.edges_count(), 10);
+        assert_eq!(degree_sequence(&wheel), vec![5, 3, 3, 3, 3, 3]);
+
+        let k33: Graph16 = complete_bipartite(3, 3).unwrap();
+        assert_eq!(k33.edges_count(), 9);
+        assert_eq!(girth(&k33), Some(4));
+
+        let cube: Graph16 = hypercube(4).unwrap();
+        assert_eq!(degree_sequence(&cube), vec![4; 16]);
+        assert_eq!(grid::<Graph16>(3, 4).unwrap().edges_count(), 17);
+
+        // The Möbius ladder on 6 nodes is K3,3
+        let ladder: Graph16 = mobius_ladder(3).unwrap();
+        assert_eq!(ladder.to_canonical(), k33.to_canonical());
+
+        let petersen: Graph16 = petersen().unwrap();
+        assert_eq!(degree_sequence(&petersen), vec![3; 10]);
+        assert_eq!(girth(&petersen), Some(5));
+        let prism: Graph16 = generalized_petersen(3, 1).unwrap();
+        assert_eq!(prism.edges_count(), 9);
+        assert_eq!(circulant::<Graph16>(7, &[1, 2, 3]).unwrap(), Graph16::complete(7));
+    }
+
+    #[test]
+    fn too_many_nodes() {
+        assert_eq!(cycle::<Graph16>(17), Err(TooManyNodes { n: 17, maxn: 16 }));
+        assert_eq!(hypercube::<Graph32>(6), Err(TooManyNodes { n: 64, maxn: 32 }));
+        assert!(hypercube::<Graph64>(6).is_ok());
+        assert!(grid::<Graph32>(6, 6).is_err());
+        assert!(complete_bipartite::<Graph16>(8, 9).is_err());
+        assert!(generalized_petersen::<Graph16>(9, 2).is_err());
+
+        // Sizes that overflow are reported as saturated
+        let overflow = Err(TooManyNodes { n: usize::MAX, maxn: 16 });
+        assert_eq!(grid::<Graph16>(usize::MAX / 2 + 1, 2), overflow);
+        assert_eq!(complete_bipartite::<Graph16>(usize::MAX, 2), overflow);
+        assert_eq!(star::<Graph16>(usize::MAX), overflow);
+        assert_eq!(generalized_petersen::<Graph16>(usize::MAX, 2), overflow);
+        assert_eq!(mobius_ladder::<Graph16>(usize::MAX / 2 + 1), overflow);
+        let bits = usize::BITS as usize;
+        assert_eq!(hypercube::<Graph16>(bits), overflow);
+        assert_eq!(hypercube::<Graph16>(bits - 1), Err(TooManyNodes { n: 1 << (bits - 1), maxn: 16 }));
+    }
+
+    #[test]
+    fn petersen_family() {
+        let family = super::petersen_family::<Graph16>().unwrap();
+        assert_eq!(family.len(), 7);
+        assert!(family.iter().all(|graph| graph.edges_count() == 15));
+        assert_eq!(family[0], Graph16::complete(6).to_canonical());
+        assert_eq!(family[6], petersen::<Graph16>().unwrap().to_canonical());
+    }
+}
@@ -4,18 +4,23 @@ use crate::seq::{self, Seq};
 use crate::permutation::{Permutation, SmallPerm};
 use crate::embedding::{RotationSystem, SmallRotationSystem};
 
-#[cfg(all(target_arch = "x86_64", target_feature="avx2"))]
-pub mod avx2;
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+mod graph16;
+pub mod rows;
+pub mod dynamic;
+
+pub use graph16::Graph16;
+pub use rows::BitsetRows;
+pub use dynamic::{DynGraph, DynColoring};
 
-#[cfg(all(target_arch = "x86_64", target_feature="avx2"))]
-pub use avx2::Graph16;
-#[cfg(not(all(target_arch = "x86_64", target_feature="avx2")))]
-pub type Graph16 = BitsetGraph<bitset::Bitset16, 16>;
 pub type Graph32 = BitsetGraph<bitset::Bitset32, 32>;
 pub type Graph64 = BitsetGraph<bitset::Bitset64, 64>;
+pub type Graph128 = BitsetGraph<bitset::Bitset128, 128>;
 pub type Coloring16 = SmallColoring<bitset::Bitset16, 16>;
 pub type Coloring32 = SmallColoring<bitset::Bitset32, 32>;
 pub type Coloring64 = SmallColoring<bitset::Bitset64, 64>;
+pub type Coloring128 = SmallColoring<bitset::Bitset128, 128>;
 
 pub fn subgraphs<'a, G: Graph + Clone>(graph: &'a G) -> impl 'a + Iterator<Item = G> {
     graph.nodes().iter().map(move |u| {
@@ -188,6 +193,7 @@ pub trait Graph: Sized + Clone {
 
     fn union(&mut self, other: &Self);
 
+    /// Keeps the nodes of `self`, removes the edges of `other`.
     fn difference(&mut self, other: &Self);
 
     #[inline]
@@ -239,6 +245,70 @@ pub trait Graph: Sized + Clone {
         self == &self.clone().to_canonical()
     }
 
+    /// Canonical form when node `u` has colour `colors[u]`, together with the
+    /// canonical relabelling and the automorphisms that keep the colours
+    ///
+    /// Panics if `colors` does not cover every node label.
+    fn to_canonical_with_colors(self, colors: &[usize]) -> crate::iso::SearchResults<Self>
+        where Self: Ord, Self::Perm: Eq + Hash, Self::Path: Eq + Hash
+    {
+        crate::iso::search_tree_with_colors(self, colors)
+    }
+
+    /// Is there an isomorphism to `other` taking the colour of every node to
+    /// the same colour
+    ///
+    /// Both colourings are indexed by node label, panics if one of them does
+    /// not cover every node of its graph.
+    fn is_isomorphic_colored(&self, colors: &[usize], other: &Self, other_colors: &[usize]) -> bool
+        where Self: Ord, Self::Perm: Eq + Hash, Self::Path: Eq + Hash
+    {
+        let certificate = |graph: &Self, colors: &[usize]| {
+            let res = crate::iso::search_tree_with_colors(graph.clone(), colors);
+            let mut canonical_colors: Vec<_> = graph.nodes().iter()
+                .map(|u| (res.canonical_relabeling.get(u), colors[u]))
+                .collect();
+            canonical_colors.sort_unstable();
+            (res.canonical_graph, canonical_colors)
+        };
+        certificate(self, colors) == certificate(other, other_colors)
+    }
+
+    /// The multigraph without nodes of degree two, see `Multigraph::smooth`
+    fn smooth(&self) -> crate::multigraph::Multigraph<Self> {
+        crate::multigraph::Multigraph::from_graph(self).smooth()
+    }
+
+    /// Suppresses nodes of degree two as long as the graph stays simple,
+    /// together with the nodes of degree two that are left because their
+    /// neighbours are adjacent. Which of them are left depends on the order.
+    fn smooth_simple(&self) -> (Self, Self::Set) {
+        let mut graph = self.clone();
+        let ends = |graph: &Self, u| -> Option<(usize, usize)> {
+            let mut siblings = graph.siblings(u).iter();
+            match (siblings.next(), siblings.next(), siblings.next()) {
+                (Some(a), Some(b), None) if !graph.has_edge(a, b) => Some((a, b)),
+                _ => None,
+            }
+        };
+        while let Some((u, (a, b))) = graph.nodes().iter().find_map(|u| ends(&graph, u).map(|e| (u, e))) {
+            graph.del_node(u);
+            graph.add_edge(a, b);
+        }
+
+        let mut kept = Self::Set::new();
+        for u in graph.nodes().iter().filter(|&u| graph.siblings(u).count() == 2) {
+            kept.set(u);
+        }
+        (graph, kept)
+    }
+
+    /// Do both graphs become isomorphic after smoothing, that is are they
+    /// subdivisions of the same multigraph
+    fn is_homeomorphic(&self, other: &Self) -> bool where Self: Ord {
+        self.smooth().to_canonical() == other.smooth().to_canonical()
+    }
+
     fn is_planar(&self) -> bool {
         if self.is_connected() {
             crate::planar::fastdmp(self).is_some()
@@ -439,7 +509,7 @@ pub struct BitsetGraph<B, const N: usize> {
     g: [B; N],
 }
 
-impl<B: Bitset + Copy, const N: usize> BitsetGraph<B, N> {
+impl<B: BitsetRows, const N: usize> BitsetGraph<B, N> {
     pub fn from_raw(raw: &[B]) -> Self {
         let mut graph = Self::empty();
         for (i, u) in raw.iter().enumerate() {
@@ -453,7 +523,7 @@ impl<B: Bitset + Copy, const N: usize> BitsetGraph<B, N> {
     }
 }
 
-impl<B: Bitset + Copy, const N: usize> Graph for BitsetGraph<B, N> {
+impl<B: BitsetRows, const N: usize> Graph for BitsetGraph<B, N> {
     const MAXN: usize = N;
     type Perm = B::Perm;
     type Set = B;
@@ -558,48 +628,29 @@ impl<B: Bitset + Copy, const N: usize> Graph for BitsetGraph<B, N> {
 
     #[inline]
     fn shuffle(&mut self, permutation: &Self::Perm) {
-        let old = self.g;
-
-        for (i, j) in permutation.iter() {
-            let mut bitset = old[i];
-            bitset.shuffle(permutation);
-            self.g[j] = bitset;
-        }
+        self.g = B::shuffle_rows(&self.g, permutation);
     }
 
-
     #[inline]
     fn subgraph(&self, selected: &Self::Set) -> Self {
-        let mut new = Self::empty();
-        for i in selected.iter() {
-            new.g[i] = self.g[i].intersection(selected);
+        Self {
+            g: B::subgraph_rows(&self.g, selected),
         }
-        new
     }
 
     #[inline]
     fn is_supergraph(&self, other: &Self) -> bool {
-        let mut res = true;
-        for i in 0..N {
-            res = res && self.g[i].is_superset(&other.g[i]);
-        }
-        res
+        B::is_supergraph_rows(&self.g, &other.g)
     }
 
     #[inline]
     fn union(&mut self, other: &Self) {
-        for i in 0..N {
-            self.g[i] = self.g[i].union(&other.g[i]);
-        }
+        self.g = B::union_rows(&self.g, &other.g);
     }
 
     #[inline]
     fn difference(&mut self, other: &Self) {
-        // FIXME: something is wrong here
-        for i in 0..N {
-            self.g[i] = self.g[i].difference(&other.g[i]);
-            self.g[i].set_val(i, !(self.g[i].get(i) && other.g[i].get(i)));
-        }
+        self.g = B::difference_rows(&self.g, &other.g);
     }
 
     #[inline]
@@ -617,7 +668,7 @@ impl<B: Bitset + Copy, const N: usize> Graph for BitsetGraph<B, N> {
     }
 }
 
-impl<B: Bitset<Perm = SmallPerm<N>> + Copy, const N: usize> std::fmt::Debug for BitsetGraph<B, N> {
+impl<B: BitsetRows<Perm = SmallPerm<N>>, const N: usize> std::fmt::Debug for BitsetGraph<B, N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_map().entries(self.nodes().iter().map(|u| {
             (u, self.siblings(u).iter().collect::<Vec<_>>())
@@ -801,3 +852,27 @@ impl<S: Bitset, const N: usize> Coloring for SmallColoring<S, N> {
         }).enumerate())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn difference_keeps_nodes<G: Graph>() {
+        let mut g = G::complete(4);
+        g.difference(&G::complete(2));
+
+        assert_eq!(g.nodes().count(), 4);
+        assert!(!g.has_node(4));
+        assert!(!g.has_edge(0, 1));
+        assert_eq!(g.edges().count(), 5);
+    }
+
+    #[test]
+    fn difference_without_phantom_nodes() {
+        difference_keeps_nodes::<Graph16>();
+        difference_keeps_nodes::<Graph32>();
+        difference_keeps_nodes::<Graph64>();
+        difference_keeps_nodes::<Graph128>();
+        difference_keeps_nodes::<DynGraph>();
+    }
+}
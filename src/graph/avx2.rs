@@ -1,484 +1,336 @@
+//! AVX2 kernels for `Graph16`, `Graph32` and `Graph64`. Each row of the
+//! adjacency matrix is one lane of a 256 bit register, `Graph16` fits in a
+//! single register while the larger graphs are processed 8 or 4 rows at a time.
+//!
+//! All functions require AVX2, callers only use them after checking for it at
+//! runtime.
+
 use core::arch::x86_64::*;
-use std::mem::MaybeUninit;
-use super::{SmallColoring, Graph};
-use crate::prelude::*;
-use crate::bitset::Bitset16;
-use crate::seq::SmallSeq;
-use crate::permutation::SmallPerm;
-use crate::embedding::SmallRotationSystem;
-
-const NODEMASK: Graph16 = {
+
+const NODEMASK: [u16; 16] = {
     let mut g = [0; 16];
     let mut i = 0;
     while i < 16 {
         g[i] = 1 << i;
         i += 1;
     }
-    Graph16 {
-        g
-    }
+    g
 };
 
-#[inline(always)]
-fn u16_to_mask(m: u16) -> __m256i {
-    unsafe {
-        let nodemask = NODEMASK.to_simd();
-        let m = _mm256_set1_epi16(m as i16);
-        let m = _mm256_and_si256(m, nodemask);
-        _mm256_cmpgt_epi16(m, _mm256_setzero_si256())
-    }
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load(g: &[u16; 16]) -> __m256i {
+    _mm256_loadu_si256(g.as_ptr() as *const __m256i)
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-#[repr(align(32))]
-pub struct Graph16 {
-    g: [u16; 16],
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn store(g: __m256i) -> [u16; 16] {
+    let mut res = [0; 16];
+    _mm256_storeu_si256(res.as_mut_ptr() as *mut __m256i, g);
+    res
 }
 
-impl Graph16 {
-    //#[inline]
-    pub fn from_raw(raw: &[u16]) -> Self {
-        unsafe {
-            assert!(raw.len() == 16);
-            let g = _mm256_loadu_si256(raw.as_ptr() as *const __m256i);
-            Self::from_simd(g)
-        }
-    }
-
-    //#[inline]
-    pub fn to_raw(self) -> [u16; 16] {
-        self.g
-    }
-
-    #[inline(always)]
-    fn to_simd(&self) -> __m256i {
-        unsafe { _mm256_load_si256(self.g.as_ptr() as *const __m256i) }
-    }
-
-    #[inline(always)]
-    fn from_simd(g: __m256i) -> Self {
-        unsafe {
-            let mut res: MaybeUninit<Self> = MaybeUninit::uninit();
-            _mm256_store_si256(res.as_mut_ptr() as *mut __m256i, g);
-            res.assume_init()
-        }
-    }
+/// All ones in the lanes of the nodes in `m`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn u16_to_mask(m: u16) -> __m256i {
+    let nodemask = load(&NODEMASK);
+    let m = _mm256_set1_epi16(m as i16);
+    let m = _mm256_and_si256(m, nodemask);
+    // Compare for equality, a signed greater than zero misses node 15
+    _mm256_cmpeq_epi16(m, nodemask)
 }
 
-impl Graph for Graph16 {
-    const MAXN: usize = 16;
-    type Perm = SmallPerm<16>;
-    type Set = Bitset16;
-    type Path = SmallSeq<16>;
-    type Coloring = SmallColoring<Bitset16, 16>;
-    type Embedding = SmallRotationSystem<Bitset16, Self, 16>;
-
-    fn empty() -> Self {
-        Self {
-            g: [0; 16],
-        }
-    }
-
-    #[inline]
-    fn is_empty(&self) -> bool {
-        unsafe {
-            let g = self.to_simd();
-            _mm256_testz_si256(g, _mm256_setzero_si256()) != 0
-        }
-    }
+/// Bitwise or of all lanes
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn or_lanes(g: __m256i) -> u16 {
+    // Using shuffle should be faster than extract
+    let a = _mm256_extracti128_si256(g, 0);
+    let b = _mm256_extracti128_si256(g, 1);
 
-    #[inline]
-    fn add_node(&mut self, u: usize) {
-        self.g[u] |= 1 << u;
-    }
-
-    #[inline]
-    fn del_node(&mut self, u: usize) {
-        self.g[u] = 0;
-        /*
-        for i in 0..16 {
-            self.g[i] &= !(1 << u);
-        }
-        */
-        // TODO: compiler can do this
-        unsafe {
-            let g = self.to_simd();
-            let m = _mm256_set1_epi16(!(1 << u));
-            let r = _mm256_and_si256(g, m);
-            *self = Self::from_simd(r);
-        }
-    }
+    let a = _mm_or_si128(a, b);
+    let b = _mm_shuffle_epi32(a, 78);
 
-    #[inline]
-    fn del_nodes(&mut self, nodes: &Bitset16) {
-        unsafe {
-            let mask = u16_to_mask(nodes.invert().to_u16());
-            let g = self.to_simd();
-            let g = _mm256_and_si256(g, mask);
-            let m = _mm256_set1_epi16(nodes.invert().to_u16() as i16);
-            let g = _mm256_and_si256(g, m);
-            *self = Self::from_simd(g);
-        }
+    let a = _mm_or_si128(a, b);
+    let b = _mm_shuffle_epi32(a, 229);
 
-        /*
-        for u in nodes.iter() {
-            self.g[u] = 0;
-        }
-        let inv = nodes.invert().to_u16();
-        for i in 0..16 {
-            self.g[i] = self.g[i] & inv;
-        }
-        */
-    }
+    let a = _mm_or_si128(a, b);
+    let b = _mm_srli_epi32(a, 16);
 
-    #[inline]
-    fn has_node(&self, u: usize) -> bool {
-        self.g[u] & (1 << u) != 0
-    }
-
-    #[inline]
-    fn nodes(&self) -> Bitset16 {
-        // Autovectorization is slightly unreliable
-        unsafe {
-            let m = NODEMASK.to_simd();
-            let g = self.to_simd();
+    let a = _mm_or_si128(a, b);
+    _mm_extract_epi16(a, 0) as u16
+}
 
-            let g = _mm256_and_si256(g, m);
-            let a = _mm256_extracti128_si256(g, 0);
-            let b = _mm256_extracti128_si256(g, 1);
+#[target_feature(enable = "avx2")]
+pub unsafe fn is_empty(g: &[u16; 16]) -> bool {
+    let g = load(g);
+    _mm256_testz_si256(g, g) != 0
+}
 
-            let a = _mm_or_si128(a, b);
-            let b = _mm_shuffle_epi32(a, 78);
+#[target_feature(enable = "avx2")]
+pub unsafe fn del_node(g: &[u16; 16], u: usize) -> [u16; 16] {
+    let mut g = *g;
+    g[u] = 0;
+    let m = _mm256_set1_epi16(!(1u16 << u) as i16);
+    store(_mm256_and_si256(load(&g), m))
+}
 
-            let a = _mm_or_si128(a, b);
-            let b = _mm_shuffle_epi32(a, 229);
+#[target_feature(enable = "avx2")]
+pub unsafe fn del_nodes(g: &[u16; 16], nodes: u16) -> [u16; 16] {
+    let mask = u16_to_mask(!nodes);
+    let g = _mm256_and_si256(load(g), mask);
+    let m = _mm256_set1_epi16(!nodes as i16);
+    store(_mm256_and_si256(g, m))
+}
 
-            let a = _mm_or_si128(a, b);
-            let b = _mm_srli_epi32(a, 16);
+#[target_feature(enable = "avx2")]
+pub unsafe fn nodes(g: &[u16; 16]) -> u16 {
+    or_lanes(_mm256_and_si256(load(g), load(&NODEMASK)))
+}
 
-            let a = _mm_or_si128(a, b);
-            let r = _mm_extract_epi16(a, 0);
+#[target_feature(enable = "avx2")]
+pub unsafe fn add_edges(g: &[u16; 16], u: usize, edges: u16) -> [u16; 16] {
+    let mut g = *g;
+    g[u] |= edges;
+    let mask = u16_to_mask(edges);
+    let m = _mm256_set1_epi16((1u16 << u) as i16);
+    let m = _mm256_and_si256(m, mask);
+    store(_mm256_or_si256(load(&g), m))
+}
 
-            Bitset16::from_u16(r as u16)
-        }
+#[target_feature(enable = "avx2")]
+pub unsafe fn del_edges(g: &[u16; 16], u: usize, edges: u16) -> [u16; 16] {
+    let mut g = *g;
+    g[u] &= !edges;
+    let mask = u16_to_mask(edges);
+    let m = _mm256_set1_epi16(!(1u16 << u) as i16);
+    let m = _mm256_and_si256(m, mask);
+    let g = load(&g);
+    let g2 = _mm256_and_si256(g, m);
+    store(_mm256_blendv_epi8(g, g2, mask))
+}
 
-        /*
-        // Compiler manages this on its own
-        let mut res = 0;
-        for i in 0..16 {
-            res |= (self.has_node(i) as u16) << i;
-        }
-        Bitset16::from_u16(res)
-        */
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn subgraph(g: &[u16; 16], selected: u16) -> [u16; 16] {
+    let mask = u16_to_mask(selected);
+    let g = _mm256_and_si256(load(g), mask);
+    let mask = _mm256_set1_epi16(selected as i16);
+    store(_mm256_and_si256(g, mask))
+}
 
-    #[inline]
-    fn add_edge(&mut self, u: usize, v: usize) {
-        debug_assert!(self.has_node(u));
-        debug_assert!(self.has_node(v));
-        self.g[u] |= 1 << v;
-        self.g[v] |= 1 << u;
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn is_supergraph(a: &[u16; 16], b: &[u16; 16]) -> bool {
+    _mm256_testc_si256(load(a), load(b)) != 0
+}
 
-    #[inline]
-    fn add_edges(&mut self, u: usize, edges: &Bitset16) {
-        self.g[u] = self.g[u] | edges.to_u16();
-        /*
-        for v in edges.iter() {
-            self.g[v] |= 1 << u;
-        }
-        */
-        unsafe {
-            let mask = u16_to_mask(edges.to_u16());
-            let m = _mm256_set1_epi16(1 << u);
-            let m = _mm256_and_si256(m, mask);
-            let g = self.to_simd();
-            let g = _mm256_or_si256(g, m);
-            *self = Self::from_simd(g);
-        }
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn bipartite_split(g: &[u16; 16], a: u16, b: u16) -> [u16; 16] {
+    let ma = _mm256_set1_epi16(a as i16);
+    let mask_a = u16_to_mask(a);
+    let mb = _mm256_set1_epi16(b as i16);
+    let mask_b = u16_to_mask(b);
+    let mask = _mm256_or_si256(mask_a, mask_b);
+    let mask = _mm256_and_si256(mask, load(&NODEMASK));
+
+    let mask_a = _mm256_and_si256(mask_a, mb);
+    let mask_b = _mm256_and_si256(mask_b, ma);
+
+    let g = load(g);
+    let gm = _mm256_and_si256(g, mask);
+    let ga = _mm256_and_si256(g, mask_a);
+    let gb = _mm256_and_si256(g, mask_b);
+    let g = _mm256_or_si256(gm, ga);
+    store(_mm256_or_si256(g, gb))
+}
 
-    #[inline]
-    fn del_edge(&mut self, u: usize, v: usize) {
-        self.g[u] &= !(1 << v);
-        self.g[v] &= !(1 << u);
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn neighbours(g: &[u16; 16], nodes: u16) -> u16 {
+    or_lanes(_mm256_and_si256(load(g), u16_to_mask(nodes)))
+}
 
-    #[inline]
-    fn del_edges(&mut self, u: usize, edges: &Bitset16) {
-        self.g[u] = self.g[u] & edges.invert().to_u16();
-        /*
-        for v in edges.iter() {
-            self.g[v] &= !(1 << u);
-        }
-        */
-        unsafe {
-            let mask = u16_to_mask(edges.to_u16());
-            let m = _mm256_set1_epi16(!(1 << u));
-            let m = _mm256_and_si256(m, mask);
-            let g = self.to_simd();
-            let g2 = _mm256_and_si256(g, m);
-            let g = _mm256_blendv_epi8(g, g2, mask);
-            *self = Self::from_simd(g);
-        }
-    }
+// Kernels for the rows of `Graph32` and `Graph64`
 
-    #[inline]
-    fn has_edge(&self, u: usize, v: usize) -> bool {
-        self.g[u] & (1 << v) != 0
+const NODEMASK32: [u32; 32] = {
+    let mut g = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        g[i] = 1 << i;
+        i += 1;
     }
+    g
+};
 
-    #[inline]
-    fn siblings(&self, u: usize) -> Self::Set {
-        Bitset16::from_u16(self.g[u] & !(1 << u))
+const NODEMASK64: [u64; 64] = {
+    let mut g = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        g[i] = 1 << i;
+        i += 1;
     }
+    g
+};
 
-    #[inline]
-    fn edges_count(&self) -> usize {
-        unsafe {
-            let ptr = self.g.as_ptr() as *const u64;
-            let raw = std::slice::from_raw_parts(ptr, 4);
-            let cnt = raw[0].count_ones()
-                    + raw[1].count_ones()
-                    + raw[2].count_ones()
-                    + raw[3].count_ones();
-            (cnt as usize - self.nodes().count()) / 2
-        }
-    }
+/// Loads the 32 bytes starting at row `i`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load_at<T>(g: &[T], i: usize) -> __m256i {
+    debug_assert!((i * std::mem::size_of::<T>() + 32) <= std::mem::size_of_val(g));
+    _mm256_loadu_si256(g.as_ptr().add(i) as *const __m256i)
+}
 
-    #[inline]
-    fn shuffle(&mut self, permutation: &Self::Perm) {
-        let old = self.g;
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn store_at<T>(g: &mut [T], i: usize, v: __m256i) {
+    debug_assert!((i * std::mem::size_of::<T>() + 32) <= std::mem::size_of_val(g));
+    _mm256_storeu_si256(g.as_mut_ptr().add(i) as *mut __m256i, v)
+}
 
-        for (i, j) in permutation.iter() {
-            let mut bitset = Bitset16::from_u16(old[i]);
-            bitset.shuffle(permutation);
-            self.g[j] = bitset.to_u16();
-        }
-    }
+/// Superset does not care about the row size, only about the bytes
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn is_superset_rows<T, const N: usize>(a: &[T; N], b: &[T; N]) -> bool {
+    let mut missing = _mm256_setzero_si256();
+    let rows = 32 / std::mem::size_of::<T>();
+    for i in (0..N).step_by(rows) {
+        let m = _mm256_andnot_si256(load_at(a, i), load_at(b, i));
+        missing = _mm256_or_si256(missing, m);
+    }
+    _mm256_testz_si256(missing, missing) != 0
+}
 
+#[target_feature(enable = "avx2")]
+pub unsafe fn subgraph32(g: &[u32; 32], selected: u32) -> [u32; 32] {
+    let mut res = [0; 32];
+    let sel = _mm256_set1_epi32(selected as i32);
+    for i in (0..32).step_by(8) {
+        let nodemask = load_at(&NODEMASK32, i);
+        let mask = _mm256_cmpeq_epi32(_mm256_and_si256(sel, nodemask), nodemask);
+        let row = _mm256_and_si256(load_at(g, i), sel);
+        store_at(&mut res, i, _mm256_and_si256(row, mask));
+    }
+    res
+}
 
-    #[inline]
-    fn subgraph(&self, selected: &Self::Set) -> Self {
-        unsafe {
-            let mask = u16_to_mask(selected.to_u16());
-            let g = self.to_simd();
-            let g = _mm256_and_si256(g, mask);
-            let mask = _mm256_set1_epi16(selected.to_u16() as i16);
-            let g = _mm256_and_si256(g, mask);
-            Self::from_simd(g)
-        }
-        /*
-        let mut new = Self::empty();
-        for i in selected.iter() {
-            new.g[i] = self.g[i] & selected.to_u16();
-        }
-        new
-        */
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn subgraph64(g: &[u64; 64], selected: u64) -> [u64; 64] {
+    let mut res = [0; 64];
+    let sel = _mm256_set1_epi64x(selected as i64);
+    for i in (0..64).step_by(4) {
+        let nodemask = load_at(&NODEMASK64, i);
+        let mask = _mm256_cmpeq_epi64(_mm256_and_si256(sel, nodemask), nodemask);
+        let row = _mm256_and_si256(load_at(g, i), sel);
+        store_at(&mut res, i, _mm256_and_si256(row, mask));
+    }
+    res
+}
 
-    #[inline]
-    fn is_supergraph(&self, other: &Self) -> bool {
-        /*
-        let mut res = true;
-        for i in 0..16 {
-            res = res && (!self.g[i] & other.g[i]) == 0;
-        }
-        res
-        */
-        unsafe {
-            let a = self.to_simd();
-            let b = other.to_simd();
-            let r = _mm256_testc_si256(a, b);
-            r != 0
-        }
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn is_supergraph32(a: &[u32; 32], b: &[u32; 32]) -> bool {
+    is_superset_rows(a, b)
+}
 
-    #[inline]
-    fn union(&mut self, other: &Self) {
-        // Compiler manages this one
-        for i in 0..16 {
-            self.g[i] = self.g[i] | other.g[i];
-        }
-    }
+#[target_feature(enable = "avx2")]
+pub unsafe fn is_supergraph64(a: &[u64; 64], b: &[u64; 64]) -> bool {
+    is_superset_rows(a, b)
+}
 
-    #[inline]
-    fn difference(&mut self, other: &Self) {
-        // FIXME: something is wrong here
-        for i in 0..16 {
-            self.g[i] = self.g[i] & !other.g[i];
-            self.g[i] |= (!(self.has_node(i) && other.has_node(i)) as u16) << i;
-        }
+#[target_feature(enable = "avx2")]
+pub unsafe fn union32(a: &[u32; 32], b: &[u32; 32]) -> [u32; 32] {
+    let mut res = [0; 32];
+    for i in (0..32).step_by(8) {
+        store_at(&mut res, i, _mm256_or_si256(load_at(a, i), load_at(b, i)));
     }
+    res
+}
 
-    #[inline]
-    fn bipartite_split(&self, a: &Self::Set, b: &Self::Set) -> Self {
-        /*
-        let mut new = Self::empty();
-        for i in a.iter() {
-            new.g[i] = self.g[i] & b.to_u16();
-            new.g[i] |= (self.has_node(i) as u16) << i;
-        }
-        for i in b.iter() {
-            new.g[i] = self.g[i] & a.to_u16();
-            new.g[i] |= (self.has_node(i) as u16) << i;
-        }
-        new
-        */
-        unsafe {
-            let ma = _mm256_set1_epi16(a.to_u16() as i16);
-            let mask_a = u16_to_mask(a.to_u16());
-            let mb = _mm256_set1_epi16(b.to_u16() as i16);
-            let mask_b = u16_to_mask(b.to_u16());
-            let mask = _mm256_or_si256(mask_a, mask_b);
-            let mask = _mm256_and_si256(mask, NODEMASK.to_simd());
-
-            let mask_a = _mm256_and_si256(mask_a, mb);
-            let mask_b = _mm256_and_si256(mask_b, ma);
-
-            let g = self.to_simd();
-            let gm = _mm256_and_si256(g, mask);
-            let ga = _mm256_and_si256(g, mask_a);
-            let gb = _mm256_and_si256(g, mask_b);
-            let g = _mm256_or_si256(gm, ga);
-            let g = _mm256_or_si256(g, gb);
-            Self::from_simd(g)
-        }
+#[target_feature(enable = "avx2")]
+pub unsafe fn union64(a: &[u64; 64], b: &[u64; 64]) -> [u64; 64] {
+    let mut res = [0; 64];
+    for i in (0..64).step_by(4) {
+        store_at(&mut res, i, _mm256_or_si256(load_at(a, i), load_at(b, i)));
     }
+    res
+}
 
-    // Overrides
-
-    #[inline]
-    fn neighbouring(&self, nodes: &Self::Set) -> Self {
-        // Using shuffle should be faster than extract
-        unsafe {
-            let g = self.to_simd();
-            let mask = u16_to_mask(nodes.to_u16());
-            let g = _mm256_and_si256(g, mask);
-            let a = _mm256_extracti128_si256(g, 0);
-            let b = _mm256_extracti128_si256(g, 1);
-            let a = _mm_or_si128(a, b);
-            let b = _mm_shuffle_epi32(a, 78);
-
-            let a = _mm_or_si128(a, b);
-            let b = _mm_shuffle_epi32(a, 229);
-
-            let a = _mm_or_si128(a, b);
-            let b = _mm_srli_epi32(a, 16);
-
-            let a = _mm_or_si128(a, b);
-            let r = _mm_extract_epi16(a, 0);
-
-            let selection = Bitset16::from_u16(r as u16);
-
-            self.subgraph(&selection)
-        }
-        /*
-        unsafe {
-            let g = self.to_simd();
-            let mask = u16_to_mask(nodes.to_u16());
-            let g = _mm256_and_si256(g, mask);
-            let a = _mm256_extracti128_si256(g, 0);
-            let b = _mm256_extracti128_si256(g, 1);
-            let g = _mm_or_si128(a, b);
-            let a = _mm_extract_epi64(g, 0);
-            let b = _mm_extract_epi64(g, 1);
-            let g = a | b;
-            let g = (g >> 32) | g;
-            let g = (g >> 16) | g;
-            let selection = Bitset16::from_u16(g as u16);
-
-            self.subgraph(&selection)
-        }
-        */
+/// Removes the edges of `b` from `a`, the nodes of `a` are kept
+#[target_feature(enable = "avx2")]
+pub unsafe fn difference32(a: &[u32; 32], b: &[u32; 32]) -> [u32; 32] {
+    let mut res = [0; 32];
+    for i in (0..32).step_by(8) {
+        let edges = _mm256_andnot_si256(load_at(&NODEMASK32, i), load_at(b, i));
+        store_at(&mut res, i, _mm256_andnot_si256(edges, load_at(a, i)));
     }
+    res
 }
 
-impl std::fmt::Debug for Graph16 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_map().entries(self.nodes().iter().map(|u| {
-            (u, self.siblings(u).iter().collect::<Vec<_>>())
-        })).finish()
+/// Removes the edges of `b` from `a`, the nodes of `a` are kept
+#[target_feature(enable = "avx2")]
+pub unsafe fn difference64(a: &[u64; 64], b: &[u64; 64]) -> [u64; 64] {
+    let mut res = [0; 64];
+    for i in (0..64).step_by(4) {
+        let edges = _mm256_andnot_si256(load_at(&NODEMASK64, i), load_at(b, i));
+        store_at(&mut res, i, _mm256_andnot_si256(edges, load_at(a, i)));
     }
+    res
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn del_nodes() {
-        let mut a = Graph16::complete(7);
-
-        let mut b = a.clone();
-
-        let mut set = Bitset16::new();
-        for i in 0..2 {
-            a.del_node(i);
-            set.set(i);
-        }
-        b.del_nodes(&set);
+/// Moves row and column `i` to `perm[i]`.
+///
+/// The rows are moved first, then column `i` of the result is collected with
+/// `movemask` and becomes row `perm[i]`, this is only correct because the
+/// adjacency matrix is symmetric.
+#[target_feature(enable = "avx2")]
+pub unsafe fn shuffle32(g: &[u32; 32], perm: &[u8; 32]) -> [u32; 32] {
+    let mut moved = [0; 32];
+    for (row, &j) in g.iter().zip(perm) {
+        moved[j as usize] = *row;
+    }
+    let regs = [load_at(&moved, 0), load_at(&moved, 8), load_at(&moved, 16), load_at(&moved, 24)];
+
+    let mut res = [0; 32];
+    for (i, &j) in perm.iter().enumerate() {
+        // Column i is empty when i is not a node
+        if g[i] & (1 << i) == 0 {
+            continue
+        }
+        let shift = _mm_cvtsi32_si128(31 - i as i32);
+        let mut col = 0;
+        for (k, reg) in regs.iter().enumerate() {
+            let bits = _mm256_castsi256_ps(_mm256_sll_epi32(*reg, shift));
+            col |= (_mm256_movemask_ps(bits) as u32) << (8 * k);
+        }
+        res[j as usize] = col;
+    }
+    res
+}
 
-        assert_eq!(a, b);
+/// Same as `shuffle32`, each register holds only 4 rows so a column takes 16
+/// `movemask`s.
+#[target_feature(enable = "avx2")]
+pub unsafe fn shuffle64(g: &[u64; 64], perm: &[u8; 64]) -> [u64; 64] {
+    let mut moved = [0; 64];
+    for (row, &j) in g.iter().zip(perm) {
+        moved[j as usize] = *row;
     }
-
-    #[test]
-    fn add_edges() {
-        let mut a = Graph16::complete(7);
-        a.add_node(7);
-
-        let mut b = a.clone();
-        let mut set = Bitset16::new();
-        for i in 0..3 {
-            a.add_edge(i, 7);
-            set.set(i);
-        }
-        b.add_edges(7, &set);
-
-        assert_eq!(a, b);
+    let mut regs = [_mm256_setzero_si256(); 16];
+    for (k, reg) in regs.iter_mut().enumerate() {
+        *reg = load_at(&moved, 4 * k);
     }
 
-    #[test]
-    fn subgraph() {
-        let g = Graph16::complete(7);
-
-        let mut set = Bitset16::new();
-        for i in 0..5 {
-            set.set(i);
+    let mut res = [0; 64];
+    for (i, &j) in perm.iter().enumerate() {
+        if g[i] & (1 << i) == 0 {
+            continue
         }
-
-        let subgraph = g.subgraph(&set);
-
-        assert_eq!(Graph16::complete(5), subgraph);
-    }
-
-    #[test]
-    fn is_supergraph() {
-        let a = Graph16::complete(5);
-        let b = Graph16::complete(7);
-        assert!(b.is_supergraph(&a));
-        assert!(!a.is_supergraph(&b));
-        assert!(a.is_supergraph(&a));
-    }
-
-    /*
-    #[test]
-    fn neighbouring() {
-        let mut a = Graph16::complete(1);
-        let mut b = Graph16::complete(1);
-
-        let mut set = Bitset16::new();
-        for i in 1..5 {
-            set.set(i);
-            g.add_node(i);
-            g.add_edge(0, i);
+        let shift = _mm_cvtsi32_si128(63 - i as i32);
+        let mut col = 0;
+        for (k, reg) in regs.iter().enumerate() {
+            let bits = _mm256_castsi256_pd(_mm256_sll_epi64(*reg, shift));
+            col |= (_mm256_movemask_pd(bits) as u64) << (4 * k);
         }
-
-        let a = Graph16::complete(5);
-        let b = Graph16::complete(7);
-        assert!(b.is_supergraph(&a));
-        assert!(!a.is_supergraph(&b));
-        assert!(a.is_supergraph(&a));
+        res[j as usize] = col;
     }
-    */
+    res
 }
@@ -0,0 +1,400 @@
+use super::{Graph, Coloring};
+use crate::prelude::*;
+use crate::bitset::{DynBitset, DYN_MAXN};
+use crate::seq::DynSeq;
+use crate::permutation::DynPerm;
+use crate::embedding::DynRotationSystem;
+
+/// Heap allocated graph with up to `DYN_MAXN` nodes, only the rows up to the
+/// largest node are stored.
+#[derive(Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DynGraph {
+    g: Vec<DynBitset>,
+}
+
+impl DynGraph {
+    #[inline]
+    fn normalize(&mut self) {
+        while self.g.last().map(|row| row.is_empty()).unwrap_or(false) {
+            self.g.pop();
+        }
+    }
+
+    #[inline]
+    fn row(&self, u: usize) -> Option<&DynBitset> {
+        self.g.get(u)
+    }
+
+    #[inline]
+    fn row_mut(&mut self, u: usize) -> &mut DynBitset {
+        if self.g.len() <= u {
+            self.g.resize(u + 1, DynBitset::new());
+        }
+        &mut self.g[u]
+    }
+}
+
+impl Graph for DynGraph {
+    const MAXN: usize = DYN_MAXN;
+    type Perm = DynPerm;
+    type Set = DynBitset;
+    type Path = DynSeq;
+    type Coloring = DynColoring;
+    type Embedding = DynRotationSystem<Self>;
+
+    fn empty() -> Self {
+        Self {
+            g: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.g.is_empty()
+    }
+
+    #[inline]
+    fn add_node(&mut self, u: usize) {
+        self.row_mut(u).set(u);
+    }
+
+    #[inline]
+    fn del_node(&mut self, u: usize) {
+        if let Some(row) = self.g.get_mut(u) {
+            *row = DynBitset::new();
+        }
+        for row in &mut self.g {
+            row.clear(u);
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn del_nodes(&mut self, nodes: &DynBitset) {
+        for u in nodes.iter() {
+            if let Some(row) = self.g.get_mut(u) {
+                *row = DynBitset::new();
+            }
+        }
+        for row in &mut self.g {
+            *row = row.difference(nodes);
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn has_node(&self, u: usize) -> bool {
+        self.row(u).map(|row| row.get(u)).unwrap_or(false)
+    }
+
+    #[inline]
+    fn nodes(&self) -> DynBitset {
+        let mut res = DynBitset::new();
+        for (i, row) in self.g.iter().enumerate() {
+            if row.get(i) {
+                res.set(i);
+            }
+        }
+        res
+    }
+
+    #[inline]
+    fn add_edge(&mut self, u: usize, v: usize) {
+        debug_assert!(self.has_node(u));
+        debug_assert!(self.has_node(v));
+        self.row_mut(u).set(v);
+        self.row_mut(v).set(u);
+    }
+
+    #[inline]
+    fn add_edges(&mut self, u: usize, edges: &DynBitset) {
+        let row = self.row_mut(u);
+        *row = row.union(edges);
+        for v in edges.iter() {
+            self.row_mut(v).set(u);
+        }
+    }
+
+    #[inline]
+    fn del_edge(&mut self, u: usize, v: usize) {
+        if let Some(row) = self.g.get_mut(u) {
+            row.clear(v);
+        }
+        if let Some(row) = self.g.get_mut(v) {
+            row.clear(u);
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn del_edges(&mut self, u: usize, edges: &DynBitset) {
+        if let Some(row) = self.g.get_mut(u) {
+            *row = row.difference(edges);
+        }
+        for v in edges.iter() {
+            if let Some(row) = self.g.get_mut(v) {
+                row.clear(u);
+            }
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn has_edge(&self, u: usize, v: usize) -> bool {
+        self.row(u).map(|row| row.get(v)).unwrap_or(false)
+    }
+
+    #[inline]
+    fn siblings(&self, u: usize) -> DynBitset {
+        let mut res = self.row(u).cloned().unwrap_or_default();
+        res.clear(u);
+        res
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &DynPerm) {
+        let old = std::mem::take(&mut self.g);
+
+        for (i, mut row) in old.into_iter().enumerate() {
+            if !row.is_empty() {
+                row.shuffle(permutation);
+                *self.row_mut(permutation.get(i)) = row;
+            }
+        }
+    }
+
+    #[inline]
+    fn subgraph(&self, selected: &DynBitset) -> Self {
+        let mut new = Self::empty();
+        for i in selected.iter() {
+            if let Some(row) = self.row(i) {
+                *new.row_mut(i) = row.intersection(selected);
+            }
+        }
+        new.normalize();
+        new
+    }
+
+    #[inline]
+    fn is_supergraph(&self, other: &Self) -> bool {
+        other.g.iter().enumerate().all(|(i, row)| {
+            self.row(i).map(|r| r.is_superset(row)).unwrap_or_else(|| row.is_empty())
+        })
+    }
+
+    #[inline]
+    fn union(&mut self, other: &Self) {
+        for (i, row) in other.g.iter().enumerate() {
+            let r = self.row_mut(i);
+            *r = r.union(row);
+        }
+    }
+
+    #[inline]
+    fn difference(&mut self, other: &Self) {
+        for (i, row) in self.g.iter_mut().enumerate() {
+            if let Some(other_row) = other.g.get(i) {
+                let has_node = row.get(i);
+                *row = row.difference(other_row);
+                row.set_val(i, has_node);
+            }
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn bipartite_split(&self, a: &DynBitset, b: &DynBitset) -> Self {
+        let mut new = Self::empty();
+        for (x, y) in &[(a, b), (b, a)] {
+            for i in x.iter() {
+                if let Some(row) = self.row(i) {
+                    let mut split = row.intersection(y);
+                    split.set_val(i, row.get(i));
+                    *new.row_mut(i) = split;
+                }
+            }
+        }
+        new.normalize();
+        new
+    }
+}
+
+impl std::fmt::Debug for DynGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.nodes().iter().map(|u| {
+            (u, self.siblings(u).iter().collect::<Vec<_>>())
+        })).finish()
+    }
+}
+
+/// Coloring for `DynGraph`, stores the color of each node up to the largest
+/// colored one.
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+pub struct DynColoring {
+    colors: Vec<usize>,
+}
+
+impl std::fmt::Debug for DynColoring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.colors)
+    }
+}
+
+impl Coloring for DynColoring {
+    type Set = DynBitset;
+    type Perm = DynPerm;
+
+    fn new() -> Self {
+        Self {
+            colors: Vec::new(),
+        }
+    }
+
+    fn defined(&self, u: usize) -> bool {
+        self.colors.get(u).map(|c| *c != 0).unwrap_or(false)
+    }
+
+    fn set(&mut self, u: usize, c: usize) {
+        debug_assert!(c < DYN_MAXN);
+        if self.colors.len() <= u {
+            self.colors.resize(u + 1, 0);
+        }
+        self.colors[u] = c + 1;
+    }
+
+    fn get(&self, u: usize) -> usize {
+        self.colors[u] - 1
+    }
+
+    fn next_color(&self) -> usize {
+        self.colors.iter().max().copied().unwrap_or(0)
+    }
+
+    fn cells(&self) -> DynBitset {
+        let mut set = DynBitset::new();
+        for c in &self.colors {
+            if *c > 0 {
+                set.set(c - 1);
+            }
+        }
+        set
+    }
+
+    fn get_cell(&self, cell: usize) -> DynBitset {
+        let mut set = DynBitset::new();
+        for (i, c) in self.colors.iter().enumerate() {
+            if *c == cell + 1 {
+                set.set(i);
+            }
+        }
+        set
+    }
+
+    fn discrete(&self) -> bool {
+        let mut set = DynBitset::new();
+
+        for c in &self.colors {
+            if *c > 0 && set.get(c - 1) {
+                return false
+            } else if *c > 0 {
+                set.set(c - 1);
+            }
+        }
+
+        true
+    }
+
+    fn individualize(&mut self, u: usize) {
+        let cu = self.colors[u];
+        for (v, c) in self.colors.iter_mut().enumerate() {
+            if u != v && *c >= cu {
+                *c += 1;
+            }
+        }
+    }
+
+    fn permutation(&self) -> Option<DynPerm> {
+        // FIXME: assumes coloring is consecutive
+
+        let mut end = self.colors.len();
+        DynPerm::from_iter(self.colors.iter().map(|c| {
+            if *c == 0 {
+                end -= 1;
+                end
+            } else {
+                c - 1
+            }
+        }).enumerate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph64;
+    use crate::{planar, toroidal, random};
+    use crate::families::grid;
+
+    #[test]
+    fn matches_graph64() {
+        for _ in 0..10 {
+            let graph: Graph64 = random::graph(40);
+            let dyn_graph: DynGraph = graph.convert();
+            assert_eq!(dyn_graph.edges_count(), graph.edges_count());
+            assert_eq!(dyn_graph.is_planar(), graph.is_planar());
+            assert_eq!(dyn_graph.to_canonical().convert::<Graph64>(), graph.to_canonical());
+        }
+    }
+
+    #[test]
+    fn normalized() {
+        let mut graph = DynGraph::empty();
+        graph.add_node(0);
+        graph.add_node(300);
+        graph.add_edge(0, 300);
+        graph.del_node(300);
+
+        let mut k1 = DynGraph::empty();
+        k1.add_node(0);
+        assert_eq!(graph, k1);
+        graph.del_node(0);
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn large_planar() {
+        let graph: DynGraph = grid(15, 20).unwrap();
+        let embedding = planar::find_embedding(&graph).unwrap();
+        assert_eq!(embedding.genus(), 0);
+        assert_eq!(embedding.to_graph(), graph);
+
+        let mut graph = graph;
+        graph.add_edge(0, 299);
+        graph.add_edge(19, 280);
+        assert!(!graph.is_planar());
+    }
+
+    #[test]
+    fn large_toroidal() {
+        // A 10x10 grid with wrap-around edges is a torus
+        let mut graph: DynGraph = grid(10, 10).unwrap();
+        for i in 0..10 {
+            graph.add_edge(i, 90 + i);
+            graph.add_edge(i*10, i*10 + 9);
+        }
+        let embedding = toroidal::find_embedding(&graph).unwrap();
+        assert_eq!(embedding.genus(), 1);
+        assert_eq!(embedding.to_graph(), graph);
+    }
+
+    #[test]
+    fn large_canonical() {
+        let graph: DynGraph = grid(10, 12).unwrap();
+        let mut shuffled = graph.clone();
+        let perm: DynPerm = random::permutation(200);
+        shuffled.shuffle(&perm);
+        assert_ne!(shuffled, graph);
+        assert_eq!(shuffled.to_canonical(), graph.clone().to_canonical());
+        assert_ne!(grid::<DynGraph>(8, 15).unwrap().to_canonical(), graph.to_canonical());
+    }
+}
@@ -0,0 +1,422 @@
+use super::{SmallColoring, Graph};
+use crate::prelude::*;
+use crate::bitset::Bitset16;
+use crate::seq::SmallSeq;
+use crate::permutation::SmallPerm;
+use crate::embedding::SmallRotationSystem;
+
+/// Uses the kernel from `avx2` when the cpu supports it and the one from
+/// `scalar` otherwise. With `-C target-cpu=native` on an AVX2 machine the
+/// check is resolved at compile time.
+macro_rules! dispatch {
+    ($name:ident($($arg:expr),*)) => {{
+        #[cfg(target_arch = "x86_64")]
+        let res = if is_x86_feature_detected!("avx2") {
+            unsafe { super::avx2::$name($($arg),*) }
+        } else {
+            scalar::$name($($arg),*)
+        };
+        #[cfg(not(target_arch = "x86_64"))]
+        let res = scalar::$name($($arg),*);
+        res
+    }}
+}
+
+/// Portable versions of the kernels in `avx2`, the results must be identical
+mod scalar {
+    pub fn is_empty(g: &[u16; 16]) -> bool {
+        g.iter().all(|row| *row == 0)
+    }
+
+    pub fn del_node(g: &[u16; 16], u: usize) -> [u16; 16] {
+        let mut g = *g;
+        g[u] = 0;
+        for row in &mut g {
+            *row &= !(1 << u);
+        }
+        g
+    }
+
+    pub fn del_nodes(g: &[u16; 16], nodes: u16) -> [u16; 16] {
+        let mut g = *g;
+        for (i, row) in g.iter_mut().enumerate() {
+            *row = if nodes & (1 << i) != 0 { 0 } else { *row & !nodes };
+        }
+        g
+    }
+
+    pub fn nodes(g: &[u16; 16]) -> u16 {
+        let mut res = 0;
+        for (i, row) in g.iter().enumerate() {
+            res |= row & (1 << i);
+        }
+        res
+    }
+
+    pub fn add_edges(g: &[u16; 16], u: usize, edges: u16) -> [u16; 16] {
+        let mut g = *g;
+        g[u] |= edges;
+        for (i, row) in g.iter_mut().enumerate() {
+            if edges & (1 << i) != 0 {
+                *row |= 1 << u;
+            }
+        }
+        g
+    }
+
+    pub fn del_edges(g: &[u16; 16], u: usize, edges: u16) -> [u16; 16] {
+        let mut g = *g;
+        g[u] &= !edges;
+        for (i, row) in g.iter_mut().enumerate() {
+            if edges & (1 << i) != 0 {
+                *row &= !(1 << u);
+            }
+        }
+        g
+    }
+
+    pub fn subgraph(g: &[u16; 16], selected: u16) -> [u16; 16] {
+        let mut g = *g;
+        for (i, row) in g.iter_mut().enumerate() {
+            *row = if selected & (1 << i) != 0 { *row & selected } else { 0 };
+        }
+        g
+    }
+
+    pub fn is_supergraph(a: &[u16; 16], b: &[u16; 16]) -> bool {
+        a.iter().zip(b).all(|(a, b)| !a & b == 0)
+    }
+
+    pub fn bipartite_split(g: &[u16; 16], a: u16, b: u16) -> [u16; 16] {
+        let mut new = [0; 16];
+        for (i, row) in g.iter().enumerate() {
+            if a & (1 << i) != 0 {
+                new[i] |= (row & b) | (row & (1 << i));
+            }
+            if b & (1 << i) != 0 {
+                new[i] |= (row & a) | (row & (1 << i));
+            }
+        }
+        new
+    }
+
+    pub fn neighbours(g: &[u16; 16], nodes: u16) -> u16 {
+        let mut res = 0;
+        for (i, row) in g.iter().enumerate() {
+            if nodes & (1 << i) != 0 {
+                res |= row;
+            }
+        }
+        res
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[repr(align(32))]
+pub struct Graph16 {
+    g: [u16; 16],
+}
+
+impl Graph16 {
+    pub fn from_raw(raw: &[u16]) -> Self {
+        let mut g = [0; 16];
+        g.copy_from_slice(raw);
+        Self {
+            g
+        }
+    }
+
+    pub fn to_raw(self) -> [u16; 16] {
+        self.g
+    }
+}
+
+impl Graph for Graph16 {
+    const MAXN: usize = 16;
+    type Perm = SmallPerm<16>;
+    type Set = Bitset16;
+    type Path = SmallSeq<16>;
+    type Coloring = SmallColoring<Bitset16, 16>;
+    type Embedding = SmallRotationSystem<Bitset16, Self, 16>;
+
+    fn empty() -> Self {
+        Self {
+            g: [0; 16],
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        dispatch!(is_empty(&self.g))
+    }
+
+    #[inline]
+    fn add_node(&mut self, u: usize) {
+        self.g[u] |= 1 << u;
+    }
+
+    #[inline]
+    fn del_node(&mut self, u: usize) {
+        self.g = dispatch!(del_node(&self.g, u));
+    }
+
+    #[inline]
+    fn del_nodes(&mut self, nodes: &Bitset16) {
+        self.g = dispatch!(del_nodes(&self.g, nodes.to_u16()));
+    }
+
+    #[inline]
+    fn has_node(&self, u: usize) -> bool {
+        self.g[u] & (1 << u) != 0
+    }
+
+    #[inline]
+    fn nodes(&self) -> Bitset16 {
+        Bitset16::from_u16(dispatch!(nodes(&self.g)))
+    }
+
+    #[inline]
+    fn add_edge(&mut self, u: usize, v: usize) {
+        debug_assert!(self.has_node(u));
+        debug_assert!(self.has_node(v));
+        self.g[u] |= 1 << v;
+        self.g[v] |= 1 << u;
+    }
+
+    #[inline]
+    fn add_edges(&mut self, u: usize, edges: &Bitset16) {
+        self.g = dispatch!(add_edges(&self.g, u, edges.to_u16()));
+    }
+
+    #[inline]
+    fn del_edge(&mut self, u: usize, v: usize) {
+        self.g[u] &= !(1 << v);
+        self.g[v] &= !(1 << u);
+    }
+
+    #[inline]
+    fn del_edges(&mut self, u: usize, edges: &Bitset16) {
+        self.g = dispatch!(del_edges(&self.g, u, edges.to_u16()));
+    }
+
+    #[inline]
+    fn has_edge(&self, u: usize, v: usize) -> bool {
+        self.g[u] & (1 << v) != 0
+    }
+
+    #[inline]
+    fn siblings(&self, u: usize) -> Self::Set {
+        Bitset16::from_u16(self.g[u] & !(1 << u))
+    }
+
+    #[inline]
+    fn edges_count(&self) -> usize {
+        let cnt: u32 = self.g.iter().map(|row| row.count_ones()).sum();
+        (cnt as usize - self.nodes().count()) / 2
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &Self::Perm) {
+        let old = self.g;
+
+        for (i, j) in permutation.iter() {
+            let mut bitset = Bitset16::from_u16(old[i]);
+            bitset.shuffle(permutation);
+            self.g[j] = bitset.to_u16();
+        }
+    }
+
+    #[inline]
+    fn subgraph(&self, selected: &Self::Set) -> Self {
+        Self {
+            g: dispatch!(subgraph(&self.g, selected.to_u16())),
+        }
+    }
+
+    #[inline]
+    fn is_supergraph(&self, other: &Self) -> bool {
+        dispatch!(is_supergraph(&self.g, &other.g))
+    }
+
+    #[inline]
+    fn union(&mut self, other: &Self) {
+        // Compiler manages this one
+        for i in 0..16 {
+            self.g[i] |= other.g[i];
+        }
+    }
+
+    #[inline]
+    fn difference(&mut self, other: &Self) {
+        for i in 0..16 {
+            let node = self.g[i] & (1 << i);
+            self.g[i] &= !other.g[i];
+            self.g[i] |= node;
+        }
+    }
+
+    #[inline]
+    fn bipartite_split(&self, a: &Self::Set, b: &Self::Set) -> Self {
+        Self {
+            g: dispatch!(bipartite_split(&self.g, a.to_u16(), b.to_u16())),
+        }
+    }
+
+    // Overrides
+
+    #[inline]
+    fn neighbouring(&self, nodes: &Self::Set) -> Self {
+        let selection = dispatch!(neighbours(&self.g, nodes.to_u16()));
+        self.subgraph(&Bitset16::from_u16(selection))
+    }
+}
+
+impl std::fmt::Debug for Graph16 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.nodes().iter().map(|u| {
+            (u, self.siblings(u).iter().collect::<Vec<_>>())
+        })).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn del_nodes() {
+        let mut a = Graph16::complete(7);
+
+        let mut b = a;
+
+        let mut set = Bitset16::new();
+        for i in 0..2 {
+            a.del_node(i);
+            set.set(i);
+        }
+        b.del_nodes(&set);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn add_edges() {
+        let mut a = Graph16::complete(7);
+        a.add_node(7);
+
+        let mut b = a;
+        let mut set = Bitset16::new();
+        for i in 0..3 {
+            a.add_edge(i, 7);
+            set.set(i);
+        }
+        b.add_edges(7, &set);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn subgraph() {
+        let g = Graph16::complete(7);
+
+        let mut set = Bitset16::new();
+        for i in 0..5 {
+            set.set(i);
+        }
+
+        let subgraph = g.subgraph(&set);
+
+        assert_eq!(Graph16::complete(5), subgraph);
+    }
+
+    #[test]
+    fn subgraph_node_15() {
+        let mut g = Graph16::empty();
+        for i in 0..16 {
+            g.add_node(i);
+        }
+        g.add_edge(0, 15);
+        g.add_edge(14, 15);
+
+        let mut set = Bitset16::new();
+        set.set(0);
+        set.set(15);
+        let subgraph = g.subgraph(&set);
+
+        assert!(subgraph.has_node(15));
+        assert!(subgraph.has_edge(0, 15));
+        assert!(!subgraph.has_node(14));
+        assert!(!subgraph.has_edge(14, 15));
+    }
+
+    #[test]
+    fn difference() {
+        let mut a = Graph16::complete(16);
+        let mut b = Graph16::empty();
+        b.add_node(0);
+        b.add_node(15);
+        b.add_edge(0, 15);
+        a.difference(&b);
+
+        assert_eq!(a.nodes().count(), 16);
+        assert!(!a.has_edge(0, 15));
+        assert_eq!(a.edges().count(), 16 * 15 / 2 - 1);
+
+        let mut c = Graph16::complete(4);
+        c.difference(&Graph16::complete(2));
+        assert_eq!(c.nodes().count(), 4);
+        assert!(!c.has_node(4));
+    }
+
+    #[test]
+    fn is_supergraph() {
+        let a = Graph16::complete(5);
+        let b = Graph16::complete(7);
+        assert!(b.is_supergraph(&a));
+        assert!(!a.is_supergraph(&b));
+        assert!(a.is_supergraph(&a));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn avx2_matches_scalar() {
+        use super::super::avx2;
+        use rand::Rng;
+
+        if !is_x86_feature_detected!("avx2") {
+            return
+        }
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let n = rng.gen_range(0..=16);
+            let a = random::graph::<Graph16>(n).g;
+            let b = random::graph::<Graph16>(n).g;
+            let u = rng.gen_range(0..16);
+            let x: u16 = rng.gen();
+            let y: u16 = rng.gen::<u16>() & !x;
+
+            unsafe {
+                assert_eq!(avx2::is_empty(&a), scalar::is_empty(&a));
+                assert_eq!(avx2::del_node(&a, u), scalar::del_node(&a, u));
+                assert_eq!(avx2::del_nodes(&a, x), scalar::del_nodes(&a, x));
+                assert_eq!(avx2::nodes(&a), scalar::nodes(&a));
+                assert_eq!(avx2::add_edges(&a, u, x), scalar::add_edges(&a, u, x));
+                assert_eq!(avx2::del_edges(&a, u, x), scalar::del_edges(&a, u, x));
+                assert_eq!(avx2::subgraph(&a, x), scalar::subgraph(&a, x));
+                assert_eq!(avx2::is_supergraph(&a, &b), scalar::is_supergraph(&a, &b));
+                assert!(avx2::is_supergraph(&a, &avx2::subgraph(&a, x)));
+                assert_eq!(avx2::bipartite_split(&a, x, y), scalar::bipartite_split(&a, x, y));
+                assert_eq!(avx2::neighbours(&a, x), scalar::neighbours(&a, x));
+            }
+        }
+
+        let empty = [0; 16];
+        unsafe {
+            assert!(avx2::is_empty(&empty));
+            assert_eq!(avx2::nodes(&Graph16::complete(16).g), 0xffff);
+            assert_eq!(avx2::subgraph(&Graph16::complete(16).g, 0x8001), scalar::subgraph(&Graph16::complete(16).g, 0x8001));
+        }
+    }
+}
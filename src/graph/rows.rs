@@ -0,0 +1,216 @@
+//! Operations on all rows of a `BitsetGraph` at once. The defaults are plain
+//! loops over the rows, `Bitset32` and `Bitset64` override them with the AVX2
+//! kernels from `avx2` when the cpu supports it.
+
+use crate::prelude::*;
+use crate::bitset::{Bitset16, Bitset32, Bitset64, Bitset128};
+
+pub trait BitsetRows: Bitset + Copy {
+    fn subgraph_rows<const N: usize>(g: &[Self; N], selected: &Self) -> [Self; N] {
+        scalar::subgraph(g, selected)
+    }
+
+    fn is_supergraph_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> bool {
+        scalar::is_supergraph(a, b)
+    }
+
+    fn union_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> [Self; N] {
+        scalar::union(a, b)
+    }
+
+    fn difference_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> [Self; N] {
+        scalar::difference(a, b)
+    }
+
+    fn shuffle_rows<const N: usize>(g: &[Self; N], permutation: &Self::Perm) -> [Self; N] {
+        scalar::shuffle(g, permutation)
+    }
+}
+
+/// Portable versions of the row operations, the SIMD kernels must give
+/// identical results
+pub mod scalar {
+    use crate::prelude::*;
+
+    pub fn subgraph<B: Bitset + Copy, const N: usize>(g: &[B; N], selected: &B) -> [B; N] {
+        let mut new = [B::new(); N];
+        for i in selected.iter() {
+            new[i] = g[i].intersection(selected);
+        }
+        new
+    }
+
+    pub fn is_supergraph<B: Bitset + Copy, const N: usize>(a: &[B; N], b: &[B; N]) -> bool {
+        a.iter().zip(b).all(|(a, b)| a.is_superset(b))
+    }
+
+    pub fn union<B: Bitset + Copy, const N: usize>(a: &[B; N], b: &[B; N]) -> [B; N] {
+        let mut new = *a;
+        for (row, other) in new.iter_mut().zip(b) {
+            *row = row.union(other);
+        }
+        new
+    }
+
+    pub fn difference<B: Bitset + Copy, const N: usize>(a: &[B; N], b: &[B; N]) -> [B; N] {
+        let mut new = *a;
+        for (i, (row, other)) in new.iter_mut().zip(b).enumerate() {
+            *row = row.difference(other);
+            if a[i].get(i) {
+                row.set(i);
+            }
+        }
+        new
+    }
+
+    pub fn shuffle<B: Bitset + Copy, const N: usize>(g: &[B; N], permutation: &B::Perm) -> [B; N] {
+        let mut new = *g;
+        for (i, j) in permutation.iter() {
+            let mut row = g[i];
+            row.shuffle(permutation);
+            new[j] = row;
+        }
+        new
+    }
+}
+
+impl BitsetRows for Bitset16 {}
+impl BitsetRows for Bitset128 {}
+
+/// Overrides the row operations of `$name` with the AVX2 kernels, these only
+/// handle graphs of exactly `$size` rows. The bitsets are `repr(transparent)`
+/// so the rows can be reinterpreted as an array of `$type`.
+macro_rules! avx2_rows {
+    ($name:ident, $size:expr, $type:ty, $to_ty:ident,
+     $subgraph:ident, $is_supergraph:ident, $union:ident, $difference:ident, $shuffle:ident) => (
+        impl BitsetRows for $name {
+            #[inline]
+            fn subgraph_rows<const N: usize>(g: &[Self; N], selected: &Self) -> [Self; N] {
+                #[cfg(target_arch = "x86_64")]
+                if N == $size && is_x86_feature_detected!("avx2") {
+                    return unsafe {
+                        let g = &*(g as *const [Self; N] as *const [$type; $size]);
+                        std::mem::transmute_copy(&super::avx2::$subgraph(g, selected.$to_ty()))
+                    }
+                }
+                scalar::subgraph(g, selected)
+            }
+
+            #[inline]
+            fn is_supergraph_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> bool {
+                #[cfg(target_arch = "x86_64")]
+                if N == $size && is_x86_feature_detected!("avx2") {
+                    return unsafe {
+                        let a = &*(a as *const [Self; N] as *const [$type; $size]);
+                        let b = &*(b as *const [Self; N] as *const [$type; $size]);
+                        super::avx2::$is_supergraph(a, b)
+                    }
+                }
+                scalar::is_supergraph(a, b)
+            }
+
+            #[inline]
+            fn union_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> [Self; N] {
+                #[cfg(target_arch = "x86_64")]
+                if N == $size && is_x86_feature_detected!("avx2") {
+                    return unsafe {
+                        let a = &*(a as *const [Self; N] as *const [$type; $size]);
+                        let b = &*(b as *const [Self; N] as *const [$type; $size]);
+                        std::mem::transmute_copy(&super::avx2::$union(a, b))
+                    }
+                }
+                scalar::union(a, b)
+            }
+
+            #[inline]
+            fn difference_rows<const N: usize>(a: &[Self; N], b: &[Self; N]) -> [Self; N] {
+                #[cfg(target_arch = "x86_64")]
+                if N == $size && is_x86_feature_detected!("avx2") {
+                    return unsafe {
+                        let a = &*(a as *const [Self; N] as *const [$type; $size]);
+                        let b = &*(b as *const [Self; N] as *const [$type; $size]);
+                        std::mem::transmute_copy(&super::avx2::$difference(a, b))
+                    }
+                }
+                scalar::difference(a, b)
+            }
+
+            #[inline]
+            fn shuffle_rows<const N: usize>(g: &[Self; N], permutation: &Self::Perm) -> [Self; N] {
+                #[cfg(target_arch = "x86_64")]
+                if N == $size && is_x86_feature_detected!("avx2") {
+                    let mut perm = [0; $size];
+                    for (i, j) in permutation.iter() {
+                        perm[i] = j as u8;
+                    }
+                    return unsafe {
+                        let g = &*(g as *const [Self; N] as *const [$type; $size]);
+                        std::mem::transmute_copy(&super::avx2::$shuffle(g, &perm))
+                    }
+                }
+                scalar::shuffle(g, permutation)
+            }
+        }
+    )
+}
+
+avx2_rows!(Bitset32, 32, u32, to_u32, subgraph32, is_supergraph32, union32, difference32, shuffle32);
+avx2_rows!(Bitset64, 64, u64, to_u64, subgraph64, is_supergraph64, union64, difference64, shuffle64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph32, Graph64};
+    use crate::permutation::Permutation;
+    use crate::random;
+
+    fn check_rows<B: BitsetRows + std::fmt::Debug, const N: usize>(a: [B; N], b: [B; N], selected: B, permutation: B::Perm) {
+        assert_eq!(B::subgraph_rows(&a, &selected), scalar::subgraph(&a, &selected));
+        assert_eq!(B::is_supergraph_rows(&a, &b), scalar::is_supergraph(&a, &b));
+        assert!(B::is_supergraph_rows(&a, &B::subgraph_rows(&a, &selected)));
+        assert_eq!(B::union_rows(&a, &b), scalar::union(&a, &b));
+        assert_eq!(B::difference_rows(&a, &b), scalar::difference(&a, &b));
+        assert_eq!(B::shuffle_rows(&a, &permutation), scalar::shuffle(&a, &permutation));
+    }
+
+    fn random_perm<P: Permutation>(n: usize) -> P {
+        use rand::seq::SliceRandom;
+        let mut targets: Vec<usize> = (0..n).collect();
+        targets.shuffle(&mut rand::thread_rng());
+        P::from_iter(targets.into_iter().enumerate()).unwrap()
+    }
+
+    #[test]
+    fn kernels_match_scalar() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let n = rng.gen_range(0..=32);
+            let a = random::graph::<Graph32>(n).to_raw();
+            let b = random::graph::<Graph32>(rng.gen_range(0..=32)).to_raw();
+            let selected = Bitset32::from_u32(rng.gen());
+            check_rows(a, b, selected, random_perm(32));
+
+            let n = rng.gen_range(0..=64);
+            let a = random::graph::<Graph64>(n).to_raw();
+            let b = random::graph::<Graph64>(rng.gen_range(0..=64)).to_raw();
+            let selected = Bitset64::from_u64(rng.gen());
+            check_rows(a, b, selected, random_perm(64));
+        }
+
+        let complete = Graph64::complete(64).to_raw();
+        let selected = Bitset64::from_u64(0x8000_0000_0000_0001);
+        check_rows(complete, complete, selected, random_perm(64));
+    }
+
+    #[test]
+    fn difference_keeps_nodes() {
+        let mut a = Graph32::complete(6);
+        a.difference(&Graph32::complete(4));
+        assert_eq!(a.nodes(), Graph32::complete(6).nodes());
+        assert_eq!(a.edges_count(), 15 - 6);
+        assert!(!a.has_edge(0, 1));
+        assert!(a.has_edge(0, 5));
+    }
+}
@@ -0,0 +1,328 @@
+//! Isomorphism invariants computed directly on the adjacency bitsets.
+
+use crate::prelude::*;
+
+/// The degrees of all nodes in decreasing order
+pub fn degree_sequence<G: Graph>(graph: &G) -> Vec<usize> {
+    let mut degrees: Vec<_> = graph.nodes().iter().map(|u| graph.siblings(u).count()).collect();
+    degrees.sort_unstable_by(|a, b| b.cmp(a));
+    degrees
+}
+
+pub fn min_degree<G: Graph>(graph: &G) -> usize {
+    graph.nodes().iter().map(|u| graph.siblings(u).count()).min().unwrap_or(0)
+}
+
+pub fn max_degree<G: Graph>(graph: &G) -> usize {
+    graph.nodes().iter().map(|u| graph.siblings(u).count()).max().unwrap_or(0)
+}
+
+/// The length of a shortest cycle, `None` for forests
+pub fn girth<G: Graph>(graph: &G) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for root in graph.nodes().iter() {
+        let mut visited = G::Set::new();
+        visited.set(root);
+        let mut frontier = visited.clone();
+        let mut depth = 0;
+
+        // An edge inside a level closes an odd cycle through the root, two
+        // edges into the same node of the next level an even one
+        while !frontier.is_empty() && best.is_none_or(|b| 2 * depth + 1 < b) {
+            let mut next = G::Set::new();
+            let mut found = None;
+            for v in frontier.iter() {
+                let siblings = graph.siblings(v);
+                if !siblings.intersection(&frontier).is_empty() {
+                    found = Some(2 * depth + 1);
+                    break
+                }
+                let new = siblings.difference(&visited);
+                if found.is_none() && !new.intersection(&next).is_empty() {
+                    found = Some(2 * depth + 2);
+                }
+                next = next.union(&new);
+            }
+            if let Some(length) = found {
+                best = Some(best.map_or(length, |b| b.min(length)));
+                break
+            }
+            visited = visited.union(&next);
+            frontier = next;
+            depth += 1;
+        }
+    }
+    best
+}
+
+/// The largest distance from `u` to a node in its component, together with
+/// whether that component is the whole graph
+fn eccentricity<G: Graph>(graph: &G, u: usize) -> (usize, bool) {
+    let mut visited = G::Set::new();
+    visited.set(u);
+    let mut frontier = visited.clone();
+    let mut depth = 0;
+    loop {
+        let mut next = G::Set::new();
+        for v in frontier.iter() {
+            next = next.union(&graph.siblings(v));
+        }
+        next = next.difference(&visited);
+        if next.is_empty() {
+            break
+        }
+        visited = visited.union(&next);
+        frontier = next;
+        depth += 1;
+    }
+    (depth, visited.count() == graph.nodes().count())
+}
+
+/// The eccentricity of every node, `None` for empty or disconnected graphs
+fn eccentricities<G: Graph>(graph: &G) -> Option<Vec<usize>> {
+    graph.nodes().iter()
+        .map(|u| match eccentricity(graph, u) {
+            (e, true) => Some(e),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|e| !e.is_empty())
+}
+
+/// The largest distance between two nodes, `None` for empty or disconnected
+/// graphs
+pub fn diameter<G: Graph>(graph: &G) -> Option<usize> {
+    eccentricities(graph)?.into_iter().max()
+}
+
+/// The smallest eccentricity of a node, `None` for empty or disconnected
+/// graphs
+pub fn radius<G: Graph>(graph: &G) -> Option<usize> {
+    eccentricities(graph)?.into_iter().min()
+}
+
+/// The number of triangles, each is seen once from every one of its edges
+pub fn triangle_count<G: Graph>(graph: &G) -> usize {
+    let seen: usize = graph.edges()
+        .map(|(u, v)| graph.siblings(u).intersection(&graph.siblings(v)).count())
+        .sum();
+    seen / 3
+}
+
+/// Bron–Kerbosch with pivoting, `adjacent(u)` are the nodes that may share a
+/// clique with `u`
+struct Cliques<F> {
+    adjacent: F,
+    best: usize,
+}
+
+impl<S: Bitset, F: Fn(usize) -> S> Cliques<F> {
+    fn search(&mut self, size: usize, mut candidates: S, mut excluded: S) {
+        if candidates.is_empty() {
+            self.best = std::cmp::max(self.best, size);
+            return
+        }
+        if size + candidates.count() <= self.best {
+            return
+        }
+        let pivot = candidates.union(&excluded).iter()
+            .max_by_key(|&p| (self.adjacent)(p).intersection(&candidates).count())
+            .unwrap();
+        for v in candidates.difference(&(self.adjacent)(pivot)).iter() {
+            let adjacent = (self.adjacent)(v);
+            self.search(size + 1, candidates.intersection(&adjacent), excluded.intersection(&adjacent));
+            candidates.clear(v);
+            excluded.set(v);
+        }
+    }
+}
+
+fn max_clique<S: Bitset, F: Fn(usize) -> S>(nodes: S, adjacent: F) -> usize {
+    let mut cliques = Cliques {
+        adjacent,
+        best: 0,
+    };
+    cliques.search(0, nodes, S::new());
+    cliques.best
+}
+
+/// The size of a largest clique
+pub fn clique_number<G: Graph>(graph: &G) -> usize {
+    max_clique(graph.nodes(), |u| graph.siblings(u))
+}
+
+/// The size of a largest set of pairwise non-adjacent nodes
+pub fn independence_number<G: Graph>(graph: &G) -> usize {
+    let nodes = graph.nodes();
+    max_clique(nodes.clone(), |u| {
+        let mut others = nodes.difference(&graph.siblings(u));
+        others.clear(u);
+        others
+    })
+}
+
+/// Backtracking colouring that always picks the node seeing the most colours
+struct Colouring<'a, G: Graph> {
+    graph: &'a G,
+    classes: Vec<G::Set>,
+}
+
+impl<'a, G: Graph> Colouring<'a, G> {
+    fn saturation(&self, u: usize) -> usize {
+        let siblings = self.graph.siblings(u);
+        self.classes.iter().filter(|class| !class.intersection(&siblings).is_empty()).count()
+    }
+
+    fn search(&mut self, uncoloured: G::Set, k: usize) -> bool {
+        let u = match uncoloured.iter().max_by_key(|&u| {
+            (self.saturation(u), self.graph.siblings(u).intersection(&uncoloured).count())
+        }) {
+            Some(u) => u,
+            None => return true,
+        };
+        let siblings = self.graph.siblings(u);
+        let mut rest = uncoloured;
+        rest.clear(u);
+
+        for c in 0..self.classes.len() {
+            if self.classes[c].intersection(&siblings).is_empty() {
+                self.classes[c].set(u);
+                if self.search(rest.clone(), k) {
+                    return true
+                }
+                self.classes[c].clear(u);
+            }
+        }
+        // All unused colours are interchangeable, so try only one of them
+        if self.classes.len() < k {
+            let mut class = G::Set::new();
+            class.set(u);
+            self.classes.push(class);
+            if self.search(rest, k) {
+                return true
+            }
+            self.classes.pop();
+        }
+        false
+    }
+}
+
+/// The least number of colours in a proper colouring
+pub fn chromatic_number<G: Graph>(graph: &G) -> usize {
+    let nodes = graph.nodes();
+    let lower = clique_number(graph);
+    for k in lower..=max_degree(graph) {
+        let mut colouring = Colouring {
+            graph,
+            classes: Vec::new(),
+        };
+        if colouring.search(nodes.clone(), k) {
+            return k
+        }
+    }
+    // A greedy colouring never needs more
+    max_degree(graph) + 1
+}
+
+/// All invariants of a graph at once, usable as a key to bucket graphs that
+/// may be isomorphic
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct InvariantVector {
+    pub nodes: usize,
+    pub edges: usize,
+    pub degree_sequence: Vec<usize>,
+    pub girth: Option<usize>,
+    pub diameter: Option<usize>,
+    pub radius: Option<usize>,
+    pub triangles: usize,
+    pub clique_number: usize,
+    pub independence_number: usize,
+    pub chromatic_number: usize,
+}
+
+impl InvariantVector {
+    pub fn new<G: Graph>(graph: &G) -> Self {
+        Self {
+            nodes: graph.nodes().count(),
+            edges: graph.edges_count(),
+            degree_sequence: degree_sequence(graph),
+            girth: girth(graph),
+            diameter: diameter(graph),
+            radius: radius(graph),
+            triangles: triangle_count(graph),
+            clique_number: clique_number(graph),
+            independence_number: independence_number(graph),
+            chromatic_number: chromatic_number(graph),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32};
+
+    #[test]
+    fn known_graphs() {
+        let petersen = InvariantVector::new(&crate::families::petersen::<Graph16>().unwrap());
+        assert_eq!(petersen.degree_sequence, vec![3; 10]);
+        assert_eq!(petersen.girth, Some(5));
+        assert_eq!((petersen.diameter, petersen.radius), (Some(2), Some(2)));
+        assert_eq!(petersen.triangles, 0);
+        assert_eq!(petersen.clique_number, 2);
+        assert_eq!(petersen.independence_number, 4);
+        assert_eq!(petersen.chromatic_number, 3);
+
+        let k5 = InvariantVector::new(&Graph16::complete(5));
+        assert_eq!(k5.girth, Some(3));
+        assert_eq!(k5.triangles, 10);
+        assert_eq!((k5.clique_number, k5.independence_number, k5.chromatic_number), (5, 1, 5));
+
+        let c6: Graph16 = crate::families::cycle(6).unwrap();
+        assert_eq!(girth(&c6), Some(6));
+        assert_eq!(diameter(&c6), Some(3));
+        assert_eq!(chromatic_number(&c6), 2);
+
+        let path: Graph16 = crate::families::path(4).unwrap();
+        assert_eq!(girth(&path), None);
+        assert_eq!((diameter(&path), radius(&path)), (Some(3), Some(2)));
+        assert_eq!((min_degree(&path), max_degree(&path)), (1, 2));
+
+        let mut disconnected: Graph16 = crate::families::path(2).unwrap();
+        disconnected.add_node(2);
+        assert_eq!(diameter(&disconnected), None);
+        assert_eq!(chromatic_number(&Graph16::empty()), 0);
+    }
+
+    #[test]
+    fn invariant_under_shuffles() {
+        use std::collections::HashSet;
+
+        for _ in 0..20 {
+            let graph: Graph32 = crate::random::graph(12);
+            let mut shuffled = graph;
+            shuffled.shuffle(&crate::random::permutation(32));
+            assert_eq!(InvariantVector::new(&graph), InvariantVector::new(&shuffled));
+
+            let set: HashSet<_> = vec![InvariantVector::new(&graph), InvariantVector::new(&shuffled)].into_iter().collect();
+            assert_eq!(set.len(), 1);
+
+            // Brute force clique and colouring checks on the small graph
+            let nodes: Vec<_> = graph.nodes().iter().collect();
+            let mut clique = 0;
+            let mut independent = 0;
+            for mask in 0u32..(1 << nodes.len()) {
+                let chosen: Vec<_> = nodes.iter().enumerate().filter(|(i, _)| mask >> i & 1 == 1).map(|(_, &u)| u).collect();
+                let pairs = || chosen.iter().flat_map(|&u| chosen.iter().map(move |&v| (u, v))).filter(|(u, v)| u < v);
+                if pairs().all(|(u, v)| graph.has_edge(u, v)) {
+                    clique = clique.max(chosen.len());
+                }
+                if pairs().all(|(u, v)| !graph.has_edge(u, v)) {
+                    independent = independent.max(chosen.len());
+                }
+            }
+            assert_eq!(clique_number(&graph), clique);
+            assert_eq!(independence_number(&graph), independent);
+        }
+    }
+}
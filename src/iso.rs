@@ -1,14 +1,89 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::hash::Hash;
 use crate::prelude::*;
-use crate::bitset::Bitset64;
 
-pub fn refine<G: Graph>(graph: &G, mut coloring: G::Coloring, seq: G::Path) -> G::Coloring {
-    let mut cell_set = Bitset64::new();
+/// Structures that can be canonically labelled by the search tree, every
+/// `Graph` is one and so are the digraphs.
+pub trait Refinable: Clone + Ord {
+    type Perm: Permutation;
+    type Set: Bitset<Perm = Self::Perm>;
+    type Path: Seq;
+    type Coloring: Coloring<Set = Self::Set, Perm = Self::Perm>;
+
+    fn nodes(&self) -> Self::Set;
+
+    fn shuffle(&mut self, permutation: &Self::Perm);
+
+    /// Nodes of a cell are split by this value, it must be invariant under
+    /// relabelling
+    fn cell_degree(&self, u: usize, cell: &Self::Set) -> usize;
+}
+
+impl<G: Graph + Ord> Refinable for G {
+    type Perm = G::Perm;
+    type Set = G::Set;
+    type Path = G::Path;
+    type Coloring = G::Coloring;
+
+    #[inline]
+    fn nodes(&self) -> G::Set {
+        Graph::nodes(self)
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &G::Perm) {
+        Graph::shuffle(self, permutation)
+    }
+
+    #[inline]
+    fn cell_degree(&self, u: usize, cell: &G::Set) -> usize {
+        self.siblings(u).intersection(cell).count()
+    }
+}
+
+/// A graph with node colours, the colours are part of the certificate so two
+/// leaves are only equal when they also agree on the colours
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+struct Colored<G> {
+    graph: G,
+    colors: BTreeMap<usize, usize>,
+}
+
+impl<G: Refinable> Refinable for Colored<G> {
+    type Perm = G::Perm;
+    type Set = G::Set;
+    type Path = G::Path;
+    type Coloring = G::Coloring;
+
+    #[inline]
+    fn nodes(&self) -> G::Set {
+        self.graph.nodes()
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &G::Perm) {
+        self.graph.shuffle(permutation);
+        self.colors = std::mem::take(&mut self.colors).into_iter()
+            .map(|(u, c)| (permutation.get(u), c))
+            .collect();
+    }
+
+    #[inline]
+    fn cell_degree(&self, u: usize, cell: &G::Set) -> usize {
+        self.graph.cell_degree(u, cell)
+    }
+}
+
+pub fn refine<G: Refinable>(graph: &G, mut coloring: G::Coloring, seq: G::Path) -> G::Coloring {
+    let mut cell_set = G::Set::new();
     for x in seq.iter() {
         cell_set.set(x);
     }
 
+    // Nodes of a cell split by their degree into the cell of w, entries are
+    // reset after use so the buffer only grows
+    let mut frags = Vec::new();
+
     while let Some(w) = cell_set.smallest() {
         if coloring.discrete() {
             return coloring
@@ -18,28 +93,30 @@ pub fn refine<G: Graph>(graph: &G, mut coloring: G::Coloring, seq: G::Path) -> G
         let w_cell = coloring.get_cell(w);
 
         for x in coloring.cells().iter() {
-            let mut frags = [Bitset64::new(); 65];
             let x_cell = coloring.get_cell(x);
+            let mut max_edges = 0;
 
             for u in x_cell.iter() {
-                let w_edges = graph.siblings(u).intersection(&w_cell).count();
+                let w_edges = graph.cell_degree(u, &w_cell);
+                if w_edges >= frags.len() {
+                    frags.resize(w_edges + 1, G::Set::new());
+                }
                 frags[w_edges].set(u);
+                max_edges = std::cmp::max(max_edges, w_edges);
             }
-            
-            let mut x_set = Bitset64::new();
-            x_set.set(x);
 
             let col_start = coloring.next_color();
-            let mut col = col_start;
-            let mut iter = frags.iter().enumerate()
-                .filter(|(_, frag)| !frag.is_empty());
-            iter.next();
-            for (_, frag) in iter {
+            let mut iter = frags[..=max_edges].iter_mut()
+                .filter(|frag| !frag.is_empty());
+            if let Some(frag) = iter.next() {
+                *frag = G::Set::new();
+            }
+            for (col, frag) in (col_start..).zip(iter) {
                 for u in frag.iter() {
                     coloring.set(u, col);
                 }
-                cell_set.set(col as usize);
-                col += 1;
+                cell_set.set(col);
+                *frag = G::Set::new();
             }
         }
     }
@@ -47,13 +124,24 @@ pub fn refine<G: Graph>(graph: &G, mut coloring: G::Coloring, seq: G::Path) -> G
     coloring
 }
 
-pub struct SearchResults<G: Graph> {
+pub struct SearchResults<G: Refinable> {
     pub automorphisms: HashSet<G::Perm>,
     pub canonical_relabeling: G::Perm,
     pub canonical_graph: G,
 }
 
-pub fn search_tree<G: Graph + Ord>(graph: G) -> SearchResults<G> {
+impl<G: Refinable> SearchResults<G> {
+    /// The group generated by the automorphisms found in the search, acting
+    /// on the nodes of the graph
+    pub fn automorphism_group(&self) -> crate::automorphisms::AutomorphismGroup<G::Perm> {
+        let inverse = self.canonical_relabeling.invert();
+        let mut points: Vec<usize> = self.canonical_graph.nodes().iter().map(|u| inverse.get(u)).collect();
+        points.sort_unstable();
+        crate::automorphisms::AutomorphismGroup::new(points, self.automorphisms.iter().cloned())
+    }
+}
+
+pub fn search_tree<G: Refinable>(graph: G) -> SearchResults<G> {
     let mut coloring = G::Coloring::new();
 
     for u in graph.nodes().iter() {
@@ -72,7 +160,7 @@ pub fn search_tree<G: Graph + Ord>(graph: G) -> SearchResults<G> {
     }
 }
 
-pub fn search_tree_with_coloring<G: Graph + Ord>(graph: G, coloring: G::Coloring) -> SearchResults<G> {
+pub fn search_tree_with_coloring<G: Refinable>(graph: G, coloring: G::Coloring) -> SearchResults<G> {
     let mut tree = SearchTree::new(graph);
     tree.start_search(coloring);
 
@@ -85,7 +173,37 @@ pub fn search_tree_with_coloring<G: Graph + Ord>(graph: G, coloring: G::Coloring
     }
 }
 
-pub struct SearchTree<G: Graph> {
+/// Search with the nodes coloured by `colors[u]`, only automorphisms and
+/// relabellings that keep the colours are considered
+///
+/// `colors` is indexed by node label and must cover every node of the graph.
+pub fn search_tree_with_colors<G: Refinable>(graph: G, colors: &[usize]) -> SearchResults<G> {
+    let nodes = graph.nodes();
+    assert!(nodes.iter().all(|u| u < colors.len()),
+        "colors has {} entries, it needs one for every node label", colors.len());
+    let mut values: Vec<usize> = nodes.iter().map(|u| colors[u]).collect();
+    values.sort_unstable();
+    values.dedup();
+
+    let mut coloring = G::Coloring::new();
+    for u in nodes.iter() {
+        coloring.set(u, values.binary_search(&colors[u]).unwrap());
+    }
+
+    let colored = Colored {
+        colors: nodes.iter().map(|u| (u, colors[u])).collect(),
+        graph,
+    };
+    let res = search_tree_with_coloring(colored, coloring);
+
+    SearchResults {
+        automorphisms: res.automorphisms,
+        canonical_relabeling: res.canonical_relabeling,
+        canonical_graph: res.canonical_graph.graph,
+    }
+}
+
+pub struct SearchTree<G: Refinable> {
     graph: G,
     automorphisms: HashSet<G::Perm>,
     autonodes: HashSet<G::Path>,
@@ -94,7 +212,7 @@ pub struct SearchTree<G: Graph> {
     pub auto_prune: bool,
 }
 
-impl<G: Graph + Ord> SearchTree<G> {
+impl<G: Refinable> SearchTree<G> {
     pub fn new(graph: G) -> SearchTree<G> {
         SearchTree {
             graph,
@@ -227,12 +345,12 @@ impl<G: Graph + Ord> SearchTree<G> {
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-struct NodeInvariant<G: Graph> {
+struct NodeInvariant<G: Refinable> {
     seq: G::Path,
     end_graph: Option<G>,
 }
 
-impl<G: Graph + Ord> NodeInvariant<G> {
+impl<G: Refinable> NodeInvariant<G> {
     fn new() -> Self {
         Self {
             seq: G::Path::new(),
@@ -280,3 +398,71 @@ impl<G: Graph + Ord> NodeInvariant<G> {
         self.end_graph = Some(graph)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::graph::Graph16;
+    use crate::families::path;
+
+    #[test]
+    fn colored_canonical() {
+        let p4: Graph16 = path(4).unwrap();
+
+        // Colouring one end or one middle node gives different rooted paths
+        let end = [1, 0, 0, 0];
+        let other_end = [0, 0, 0, 1];
+        let middle = [0, 1, 0, 0];
+        assert!(p4.is_isomorphic_colored(&end, &p4, &other_end));
+        assert!(!p4.is_isomorphic_colored(&end, &p4, &middle));
+        assert!(!p4.is_isomorphic_colored(&end, &p4, &[2, 0, 0, 0]));
+
+        // Only the identity keeps a coloured end fixed, the plain path has the
+        // reflection too
+        let res = p4.to_canonical_with_colors(&end);
+        assert!(res.automorphisms.iter().all(|perm| (0..4).all(|u| perm.get(u) == u)));
+        assert!(p4.to_canonical_with_colors(&[0; 4]).automorphisms.iter()
+            .any(|perm| perm.get(0) == 3));
+
+        let mut relabeled = p4;
+        relabeled.shuffle(&res.canonical_relabeling);
+        assert_eq!(relabeled, res.canonical_graph);
+    }
+
+    #[test]
+    fn colored_random() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let graph: Graph16 = crate::random::graph(12);
+            let colors: Vec<usize> = (0..12).map(|_| rng.gen_range(0..3)).collect();
+
+            let mut perm = <Graph16 as Graph>::Perm::new();
+            for i in 0..12 {
+                perm.swap(i, rng.gen_range(0..12));
+            }
+            let mut shuffled = graph;
+            shuffled.shuffle(&perm);
+            let mut shuffled_colors = vec![0; 12];
+            for u in 0..12 {
+                shuffled_colors[perm.get(u)] = colors[u];
+            }
+
+            let a = graph.to_canonical_with_colors(&colors);
+            let b = shuffled.to_canonical_with_colors(&shuffled_colors);
+            assert_eq!(a.canonical_graph, b.canonical_graph);
+            assert!(graph.is_isomorphic_colored(&colors, &shuffled, &shuffled_colors));
+            for auto in &a.automorphisms {
+                assert!((0..12).all(|u| colors[auto.get(u)] == colors[u]));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "colors has 3 entries")]
+    fn colored_short_colors() {
+        let p4: Graph16 = path(4).unwrap();
+        p4.is_isomorphic_colored(&[0, 0, 0], &p4, &[0; 4]);
+    }
+}
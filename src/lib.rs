@@ -4,9 +4,19 @@ pub mod map;
 pub mod bitset;
 pub mod permutation;
 pub mod graph;
+pub mod digraph;
+pub mod multigraph;
+pub mod biconnected;
+pub mod spqr;
+pub mod invariants;
+pub mod operations;
+pub mod families;
+pub mod containment;
 pub mod viz;
+pub mod drawing;
 pub mod parse;
 pub mod iso;
+pub mod automorphisms;
 pub mod enumeration;
 pub mod random;
 pub mod embedding;
@@ -14,5 +24,7 @@ pub mod planar;
 pub mod planar_old;
 pub mod toroidal;
 pub mod prelude;
+#[cfg(feature = "serde")]
+mod serialize;
 
 //pub use graph::Graph16;
@@ -0,0 +1,594 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::prelude::*;
+
+/// A graph with parallel edges and loops. `graph` is the underlying simple
+/// graph and `mult` holds the number of edges between `u < v`, loops at `u`
+/// are stored under `(u, u)`.
+#[derive(Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Multigraph<G> {
+    graph: G,
+    mult: BTreeMap<(usize, usize), usize>,
+}
+
+/// The dart behind each edge end `(u, w)` of a subdivision at a node `u` of
+/// the multigraph
+type Darts = HashMap<(usize, usize), usize>;
+
+fn key(u: usize, v: usize) -> (usize, usize) {
+    (std::cmp::min(u, v), std::cmp::max(u, v))
+}
+
+impl<G: Graph> Multigraph<G> {
+    pub fn empty() -> Self {
+        Self {
+            graph: G::empty(),
+            mult: BTreeMap::new(),
+        }
+    }
+
+    /// Every edge of `graph` with multiplicity one
+    pub fn from_graph(graph: &G) -> Self {
+        Self {
+            graph: graph.clone(),
+            mult: graph.edges().map(|(u, v)| (key(u, v), 1)).collect(),
+        }
+    }
+
+    /// The underlying simple graph, without loops and parallel edges
+    pub fn simple(&self) -> &G {
+        &self.graph
+    }
+
+    pub fn add_node(&mut self, u: usize) {
+        self.graph.add_node(u);
+    }
+
+    pub fn del_node(&mut self, u: usize) {
+        self.graph.del_node(u);
+        self.mult.retain(|&(a, b), _| a != u && b != u);
+    }
+
+    pub fn has_node(&self, u: usize) -> bool {
+        self.graph.has_node(u)
+    }
+
+    pub fn nodes(&self) -> G::Set {
+        self.graph.nodes()
+    }
+
+    /// Adds one more edge between `u` and `v`, a loop when `u == v`
+    pub fn add_edge(&mut self, u: usize, v: usize) {
+        self.add_edges(u, v, 1);
+    }
+
+    pub fn add_edges(&mut self, u: usize, v: usize, count: usize) {
+        debug_assert!(self.has_node(u));
+        debug_assert!(self.has_node(v));
+        if count == 0 {
+            return
+        }
+        if u != v {
+            self.graph.add_edge(u, v);
+        }
+        *self.mult.entry(key(u, v)).or_insert(0) += count;
+    }
+
+    /// Removes one of the edges between `u` and `v`
+    pub fn del_edge(&mut self, u: usize, v: usize) {
+        if let Some(m) = self.mult.get_mut(&key(u, v)) {
+            *m -= 1;
+            if *m == 0 {
+                self.mult.remove(&key(u, v));
+                if u != v {
+                    self.graph.del_edge(u, v);
+                }
+            }
+        }
+    }
+
+    pub fn has_edge(&self, u: usize, v: usize) -> bool {
+        self.mult.contains_key(&key(u, v))
+    }
+
+    pub fn multiplicity(&self, u: usize, v: usize) -> usize {
+        self.mult.get(&key(u, v)).copied().unwrap_or(0)
+    }
+
+    pub fn loops(&self, u: usize) -> usize {
+        self.multiplicity(u, u)
+    }
+
+    /// Number of edge ends at `u`, loops count twice
+    pub fn degree(&self, u: usize) -> usize {
+        self.mult.iter()
+            .map(|(&(a, b), &m)| if a == u && b == u { 2 * m } else if a == u || b == u { m } else { 0 })
+            .sum()
+    }
+
+    pub fn edges_count(&self) -> usize {
+        self.mult.values().sum()
+    }
+
+    /// The pairs `u <= v` with at least one edge and their multiplicity
+    pub fn edges(&self) -> impl '_ + Iterator<Item = (usize, usize, usize)> {
+        self.mult.iter().map(|(&(u, v), &m)| (u, v, m))
+    }
+
+    /// Contracts one of the edges between `u` and `v` into `u`, the other
+    /// edges between them become loops and the edges of `v` are moved to `u`
+    /// keeping their multiplicities
+    pub fn contract_edge(&mut self, u: usize, v: usize) {
+        debug_assert!(u != v && self.has_edge(u, v));
+        self.del_edge(u, v);
+        let mut nodes = G::Set::new();
+        nodes.set(u);
+        nodes.set(v);
+        self.merge_nodes(&nodes);
+        if u > v {
+            self.swap_nodes(u, v);
+        }
+    }
+
+    /// Identifies `nodes` into the smallest of them, edges between them
+    /// become loops
+    pub fn merge_nodes(&mut self, nodes: &G::Set) {
+        let u = match nodes.smallest() {
+            Some(u) => u,
+            None => return,
+        };
+        for v in nodes.iter().filter(|&v| v != u) {
+            self.graph.del_node(v);
+        }
+        let mut merged = BTreeMap::new();
+        for (&(a, b), &m) in &self.mult {
+            let a = if nodes.get(a) { u } else { a };
+            let b = if nodes.get(b) { u } else { b };
+            if a != b {
+                self.graph.add_edge(a, b);
+            }
+            *merged.entry(key(a, b)).or_insert(0) += m;
+        }
+        self.mult = merged;
+    }
+
+    /// Exchanges the labels of `u` and `v`
+    pub fn swap_nodes(&mut self, u: usize, v: usize) {
+        let swap = |a| if a == u { v } else if a == v { u } else { a };
+        let (has_u, has_v) = (self.has_node(u), self.has_node(v));
+        let mult = std::mem::take(&mut self.mult);
+        let mut graph = self.graph.clone();
+        graph.del_node(u);
+        graph.del_node(v);
+        if has_u {
+            graph.add_node(v);
+        }
+        if has_v {
+            graph.add_node(u);
+        }
+        self.graph = graph;
+        for ((a, b), m) in mult {
+            let (a, b) = (swap(a), swap(b));
+            if a != b {
+                self.graph.add_edge(a, b);
+            }
+            self.mult.insert(key(a, b), m);
+        }
+    }
+
+    /// Replaces every node of degree two and its two edges by a single edge
+    /// between its ends, until no such node is left. Two edges to the same
+    /// node become a loop there, and a cycle ends as a node with one loop.
+    pub fn smooth(&self) -> Self {
+        let mut smooth = self.clone();
+        while let Some(u) = smooth.nodes().iter().find(|&u| smooth.degree(u) == 2 && smooth.loops(u) == 0) {
+            let ends: Vec<_> = smooth.edges()
+                .filter(|&(a, b, _)| a == u || b == u)
+                .flat_map(|(a, b, m)| std::iter::repeat_n(if a == u { b } else { a }, m))
+                .collect();
+            smooth.del_node(u);
+            smooth.add_edge(ends[0], ends[1]);
+        }
+        smooth
+    }
+
+    pub fn shuffle(&mut self, permutation: &G::Perm) {
+        self.graph.shuffle(permutation);
+        self.mult = std::mem::take(&mut self.mult).into_iter()
+            .map(|((u, v), m)| (key(permutation.get(u), permutation.get(v)), m))
+            .collect();
+    }
+
+    pub fn to_canonical(self) -> Self where G: Ord {
+        crate::iso::search_tree(self).canonical_graph
+    }
+
+    pub fn is_canonical(&self) -> bool where G: Ord {
+        self == &self.clone().to_canonical()
+    }
+
+    /// The edges as a list, each parallel edge and loop repeated. Edge `e`
+    /// has the darts `2e` at its first end and `2e + 1` at its second end.
+    pub fn edge_list(&self) -> Vec<(usize, usize)> {
+        self.edges()
+            .flat_map(|(u, v, m)| std::iter::repeat_n((u, v), m))
+            .collect()
+    }
+
+    /// The simple graph with every parallel edge but the first split by a new
+    /// node and every loop split by two, it embeds in the same surfaces as
+    /// the multigraph. `None` if it has more than `H::MAXN` nodes.
+    pub fn subdivide<H: Graph>(&self) -> Option<H> {
+        self.subdivision().map(|(graph, _)| graph)
+    }
+
+    fn subdivision<H: Graph>(&self) -> Option<(H, Darts)> {
+        let mut next = self.nodes().iter().max().map(|u| u + 1).unwrap_or(0);
+        let extra: usize = self.edges()
+            .map(|(u, v, m)| if u == v { 2 * m } else { m - 1 })
+            .sum();
+        if next + extra > H::MAXN {
+            return None
+        }
+
+        let mut graph = H::empty();
+        let mut darts = HashMap::new();
+        for u in self.nodes().iter() {
+            graph.add_node(u);
+        }
+
+        let mut new_node = |graph: &mut H| {
+            graph.add_node(next);
+            next += 1;
+            next - 1
+        };
+
+        for (e, (u, v)) in self.edge_list().into_iter().enumerate() {
+            if u == v {
+                let (a, b) = (new_node(&mut graph), new_node(&mut graph));
+                graph.add_edge(u, a);
+                graph.add_edge(a, b);
+                graph.add_edge(b, u);
+                darts.insert((u, a), 2 * e);
+                darts.insert((u, b), 2 * e + 1);
+            } else if !graph.has_edge(u, v) {
+                graph.add_edge(u, v);
+                darts.insert((u, v), 2 * e);
+                darts.insert((v, u), 2 * e + 1);
+            } else {
+                let a = new_node(&mut graph);
+                graph.add_edge(u, a);
+                graph.add_edge(a, v);
+                darts.insert((u, a), 2 * e);
+                darts.insert((v, a), 2 * e + 1);
+            }
+        }
+
+        Some((graph, darts))
+    }
+
+    /// Embeds the multigraph by embedding its subdivision, a simple graph of
+    /// type `H`, with `find`. For example
+    /// `multigraph.find_embedding::<Graph32, _>(toroidal::find_embedding)`.
+    pub fn find_embedding<H, F>(&self, find: F) -> Option<MultiEmbedding>
+        where H: Graph, F: Fn(&H) -> Option<H::Embedding>
+    {
+        let (graph, darts) = self.subdivision::<H>()?;
+        let embedding = find(&graph)?;
+
+        let mut rotations = BTreeMap::new();
+        for u in self.nodes().iter() {
+            let mut rotation = Vec::new();
+            if let Some(start) = graph.siblings(u).smallest() {
+                let mut w = start;
+                loop {
+                    rotation.push(darts[&(u, w)]);
+                    w = embedding.after(u, w);
+                    if w == start {
+                        break
+                    }
+                }
+            }
+            rotations.insert(u, rotation);
+        }
+
+        Some(MultiEmbedding {
+            edges: self.edge_list(),
+            rotations,
+        })
+    }
+}
+
+impl<G: Graph + Ord> crate::iso::Refinable for Multigraph<G> {
+    type Perm = G::Perm;
+    type Set = G::Set;
+    type Path = G::Path;
+    type Coloring = G::Coloring;
+
+    #[inline]
+    fn nodes(&self) -> G::Set {
+        self.graph.nodes()
+    }
+
+    #[inline]
+    fn shuffle(&mut self, permutation: &G::Perm) {
+        Multigraph::shuffle(self, permutation)
+    }
+
+    /// Number of edges from `u` into `cell`, loops at `u` count twice
+    #[inline]
+    fn cell_degree(&self, u: usize, cell: &G::Set) -> usize {
+        let mut degree: usize = self.graph.siblings(u).intersection(cell).iter()
+            .map(|v| self.multiplicity(u, v))
+            .sum();
+        if cell.get(u) {
+            degree += 2 * self.loops(u);
+        }
+        degree
+    }
+}
+
+impl<G: Graph> std::fmt::Debug for Multigraph<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.nodes().iter().map(|u| {
+            let edges: Vec<_> = self.edges()
+                .filter(|&(a, b, _)| a == u || b == u)
+                .map(|(a, b, m)| (if a == u { b } else { a }, m))
+                .collect();
+            (u, edges)
+        })).finish()
+    }
+}
+
+/// A rotation system of a multigraph. Edge `e` of `edges` has the darts `2e`
+/// and `2e + 1` at its first and second end, the rotation at a node is the
+/// cyclic order of the darts at it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MultiEmbedding {
+    edges: Vec<(usize, usize)>,
+    rotations: BTreeMap<usize, Vec<usize>>,
+}
+
+impl MultiEmbedding {
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    pub fn rotation(&self, u: usize) -> &[usize] {
+        &self.rotations[&u]
+    }
+
+    /// The node a dart is at
+    pub fn dart_node(&self, dart: usize) -> usize {
+        let (u, v) = self.edges[dart / 2];
+        if dart & 1 == 0 { u } else { v }
+    }
+
+    /// The dart following `dart` in the rotation at its node
+    pub fn after(&self, dart: usize) -> usize {
+        let rotation = &self.rotations[&self.dart_node(dart)];
+        let i = rotation.iter().position(|&d| d == dart).unwrap();
+        rotation[(i + 1) % rotation.len()]
+    }
+
+    /// The faces as the cyclic sequences of darts they leave nodes along
+    pub fn faces(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; 2 * self.edges.len()];
+        let mut faces = Vec::new();
+        for start in 0..visited.len() {
+            if visited[start] {
+                continue
+            }
+            let mut face = Vec::new();
+            let mut dart = start;
+            while !visited[dart] {
+                visited[dart] = true;
+                face.push(dart);
+                dart = self.after(dart ^ 1);
+            }
+            faces.push(face);
+        }
+        faces
+    }
+
+    pub fn genus(&self) -> usize {
+        // Union find over the nodes for the number of components
+        let mut parent: BTreeMap<usize, usize> = self.rotations.keys().map(|&u| (u, u)).collect();
+        fn find(parent: &mut BTreeMap<usize, usize>, u: usize) -> usize {
+            let p = parent[&u];
+            if p == u {
+                return u
+            }
+            let root = find(parent, p);
+            parent.insert(u, root);
+            root
+        }
+        for &(u, v) in &self.edges {
+            let (a, b) = (find(&mut parent, u), find(&mut parent, v));
+            parent.insert(a, b);
+        }
+        let nodes: Vec<_> = parent.keys().copied().collect();
+        let components = nodes.iter().filter(|&&u| find(&mut parent, u) == u).count();
+
+        // Isolated nodes have one face without any darts
+        let isolated = self.rotations.values().filter(|rotation| rotation.is_empty()).count();
+        let faces = self.faces().len() + isolated;
+        (2 * components + self.edges.len() - self.rotations.len() - faces) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32};
+
+    fn multi(n: usize, edges: &[(usize, usize)]) -> Multigraph<Graph16> {
+        let mut g = Multigraph::empty();
+        for u in 0..n {
+            g.add_node(u);
+        }
+        for &(u, v) in edges {
+            g.add_edge(u, v);
+        }
+        g
+    }
+
+    #[test]
+    fn multiplicities() {
+        let mut g = multi(3, &[(0, 1), (1, 0), (1, 2), (2, 2)]);
+        assert_eq!(g.multiplicity(0, 1), 2);
+        assert_eq!(g.loops(2), 1);
+        assert_eq!(g.degree(2), 3);
+        assert_eq!(g.edges_count(), 4);
+        assert_eq!(g.simple().edges_count(), 2);
+
+        g.del_edge(1, 0);
+        assert!(g.simple().has_edge(0, 1));
+        g.del_edge(0, 1);
+        assert!(!g.simple().has_edge(0, 1));
+        assert!(!g.has_edge(0, 1));
+    }
+
+    #[test]
+    fn contraction_keeps_multiplicities() {
+        let mut k3 = Multigraph::from_graph(&Graph16::complete(3));
+        k3.contract_edge(0, 1);
+        assert!(!k3.has_node(1));
+        assert_eq!(k3.multiplicity(0, 2), 2);
+        assert_eq!(k3.edges_count(), 2);
+
+        // The other edge of the pair becomes a loop
+        k3.contract_edge(2, 0);
+        assert!(k3.has_node(2));
+        assert!(!k3.has_node(0));
+        assert_eq!(k3.loops(2), 1);
+        assert_eq!(k3.edges_count(), 1);
+
+        let mut g = multi(4, &[(0, 1), (1, 2), (2, 3), (3, 0), (1, 1)]);
+        let mut nodes = crate::bitset::Bitset16::new();
+        nodes.set(1);
+        nodes.set(2);
+        nodes.set(3);
+        g.merge_nodes(&nodes);
+        assert_eq!(g.loops(1), 3);
+        assert_eq!(g.multiplicity(0, 1), 2);
+    }
+
+    #[test]
+    fn canonical() {
+        use rand::Rng;
+
+        let a = multi(3, &[(0, 1), (0, 1), (1, 2)]);
+        let b = multi(3, &[(0, 1), (1, 2), (1, 2)]);
+        let c = multi(3, &[(0, 1), (1, 2), (1, 1)]);
+        assert_eq!(a.simple(), c.simple());
+        assert_eq!(a.clone().to_canonical(), b.to_canonical());
+        assert_ne!(a.to_canonical(), c.to_canonical());
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let n = 10;
+            let mut g = multi(n, &[]);
+            for _ in 0..25 {
+                g.add_edge(rng.gen_range(0..n), rng.gen_range(0..n));
+            }
+            let mut perm = <Graph16 as Graph>::Perm::new();
+            for i in 0..n {
+                perm.swap(i, rng.gen_range(0..n));
+            }
+            let mut shuffled = g.clone();
+            shuffled.shuffle(&perm);
+            assert_eq!(g.clone().to_canonical(), shuffled.to_canonical());
+            assert!(g.to_canonical().is_canonical());
+        }
+    }
+
+    /// Replaces edge `(u, v)` by a path through the new node `w`
+    fn subdivide_edge(graph: &mut Graph32, u: usize, v: usize, w: usize) {
+        graph.del_edge(u, v);
+        graph.add_node(w);
+        graph.add_edge(u, w);
+        graph.add_edge(w, v);
+    }
+
+    #[test]
+    fn smoothing() {
+        let theta = multi(5, &[(0, 2), (2, 1), (0, 3), (3, 1), (0, 4), (4, 1)]);
+        assert_eq!(theta.smooth().to_canonical(), multi(2, &[(0, 1), (0, 1), (0, 1)]).to_canonical());
+
+        let c6: Graph16 = crate::families::cycle(6).unwrap();
+        let smooth = c6.smooth();
+        assert_eq!(smooth.nodes().count(), 1);
+        assert_eq!(smooth.edges_count(), 1);
+        assert!(c6.is_homeomorphic(&crate::families::cycle(3).unwrap()));
+        assert!(!c6.is_homeomorphic(&crate::families::path(3).unwrap()));
+
+        // The triangle left from the square cannot be smoothed any further
+        let (graph, kept) = crate::families::cycle::<Graph16>(4).unwrap().smooth_simple();
+        assert_eq!(graph.nodes().count(), 3);
+        assert_eq!(kept, graph.nodes());
+
+        let mut k4 = Graph32::complete(4);
+        for (i, (u, v)) in Graph32::complete(4).edges().enumerate() {
+            subdivide_edge(&mut k4, u, v, 10 + i);
+        }
+        let (graph, kept) = k4.smooth_simple();
+        assert_eq!(graph.edges_count(), 6);
+        assert!(kept.is_empty());
+    }
+
+    #[test]
+    fn random_homeomorphisms() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let graph: Graph32 = crate::random::graph(8);
+            let mut subdivided = graph;
+            let mut next = 8;
+            for (u, v) in graph.edges() {
+                if rng.gen_bool(0.3) && next < 32 {
+                    subdivide_edge(&mut subdivided, u, v, next);
+                    next += 1;
+                }
+            }
+            assert!(graph.is_homeomorphic(&subdivided));
+
+            if let Some((u, v)) = graph.edges().find(|&(u, v)| graph.siblings(u).count() != 2 && graph.siblings(v).count() != 2) {
+                let mut other = graph;
+                other.del_edge(u, v);
+                if other.smooth().edges_count() != graph.smooth().edges_count() {
+                    assert!(!graph.is_homeomorphic(&other));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn embeddings() {
+        // Three parallel edges make three faces in the plane
+        let theta = multi(2, &[(0, 1), (0, 1), (0, 1)]);
+        let embedding = theta.find_embedding::<Graph16, _>(crate::planar::find_embedding).unwrap();
+        assert_eq!(embedding.faces().len(), 3);
+        assert_eq!(embedding.genus(), 0);
+
+        let bouquet = multi(1, &[(0, 0), (0, 0)]);
+        let embedding = bouquet.find_embedding::<Graph16, _>(crate::planar::find_embedding).unwrap();
+        assert_eq!(embedding.rotation(0).len(), 4);
+        assert_eq!(embedding.genus(), 0);
+
+        // K5 stays toroidal with doubled edges and loops
+        let mut k5 = Multigraph::from_graph(&Graph16::complete(5));
+        k5.add_edge(0, 1);
+        k5.add_edge(2, 2);
+        assert!(k5.find_embedding::<Graph16, _>(crate::planar::find_embedding).is_none());
+        let embedding = k5.find_embedding::<Graph16, _>(crate::toroidal::find_embedding).unwrap();
+        assert_eq!(embedding.genus(), 1);
+        assert_eq!(embedding.edges().len(), 12);
+
+        // Too many nodes after subdividing
+        let mut many = multi(2, &[]);
+        many.add_edges(0, 1, 20);
+        assert!(many.subdivide::<Graph16>().is_none());
+        assert_eq!(many.subdivide::<Graph32>().unwrap().nodes().count(), 21);
+    }
+}
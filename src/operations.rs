@@ -0,0 +1,230 @@
+//! Graphs built from other graphs. Line graphs and products create new nodes
+//! and label them by the position of the original nodes in `nodes()`, so the
+//! inputs may have gaps in their labels while the result does not. Unions and
+//! joins keep the labels, including gaps, and shift those of the second graph.
+
+use crate::prelude::*;
+
+/// The result needs more nodes than the graph type can hold
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TooManyNodes {
+    pub n: usize,
+    pub maxn: usize,
+}
+
+impl std::fmt::Display for TooManyNodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "graph needs {} nodes, graph type holds at most {}", self.n, self.maxn)
+    }
+}
+
+impl std::error::Error for TooManyNodes {}
+
+/// An empty graph of type `H` with nodes `0..n`
+pub(crate) fn with_nodes<H: Graph>(n: usize) -> Result<H, TooManyNodes> {
+    if n > H::MAXN {
+        return Err(TooManyNodes { n, maxn: H::MAXN })
+    }
+    let mut graph = H::empty();
+    for u in 0..n {
+        graph.add_node(u);
+    }
+    Ok(graph)
+}
+
+/// Same nodes, and an edge exactly where `graph` has none
+pub fn complement<G: Graph>(graph: &G) -> G {
+    let nodes = graph.nodes();
+    let mut complement = G::empty();
+    for u in nodes.iter() {
+        complement.add_node(u);
+    }
+    for u in nodes.iter() {
+        let mut others = nodes.difference(&graph.siblings(u));
+        others.clear(u);
+        complement.add_edges(u, &others);
+    }
+    complement
+}
+
+/// A node for every edge of `graph`, in the order of `edges()`, adjacent when
+/// the edges share an endpoint
+pub fn line_graph<G: Graph, H: Graph>(graph: &G) -> Result<H, TooManyNodes> {
+    let edges: Vec<_> = graph.edges().collect();
+    let mut line = with_nodes::<H>(edges.len())?;
+    for (i, &(a, b)) in edges.iter().enumerate() {
+        for (j, &(c, d)) in edges.iter().enumerate().skip(i + 1) {
+            if a == c || a == d || b == c || b == d {
+                line.add_edge(i, j);
+            }
+        }
+    }
+    Ok(line)
+}
+
+/// Both graphs side by side. Nodes of `a` keep their labels and node `u` of
+/// `b` becomes `u + offset`, where `offset` is one more than the largest node
+/// of `a` or zero if `a` is empty.
+pub fn disjoint_union<A: Graph, B: Graph, H: Graph>(a: &A, b: &B) -> Result<H, TooManyNodes> {
+    let offset = a.nodes().iter().last().map_or(0, |u| u + 1);
+    let n = b.nodes().iter().last().map_or(offset, |u| offset + u + 1);
+    if n > H::MAXN {
+        return Err(TooManyNodes { n, maxn: H::MAXN })
+    }
+
+    let mut union = H::empty();
+    for u in a.nodes().iter() {
+        union.add_node(u);
+    }
+    for u in b.nodes().iter() {
+        union.add_node(u + offset);
+    }
+    for (u, v) in a.edges() {
+        union.add_edge(u, v);
+    }
+    for (u, v) in b.edges() {
+        union.add_edge(u + offset, v + offset);
+    }
+    Ok(union)
+}
+
+/// The disjoint union with every node of `a` joined to every node of `b`,
+/// labeled as in `disjoint_union`
+pub fn join<A: Graph, B: Graph, H: Graph>(a: &A, b: &B) -> Result<H, TooManyNodes> {
+    let mut join: H = disjoint_union(a, b)?;
+    let offset = a.nodes().iter().last().map_or(0, |u| u + 1);
+    for u in a.nodes().iter() {
+        for v in b.nodes().iter() {
+            join.add_edge(u, v + offset);
+        }
+    }
+    Ok(join)
+}
+
+/// The product on pairs of nodes, `(u, v)` becomes `i * |b| + j` for the
+/// positions `i` of `u` in `a` and `j` of `v` in `b`. `adjacent` gets for both
+/// coordinates whether they are equal and whether they are adjacent.
+fn product<A, B, H, F>(a: &A, b: &B, adjacent: F) -> Result<H, TooManyNodes>
+where
+    A: Graph,
+    B: Graph,
+    H: Graph,
+    F: Fn((bool, bool), (bool, bool)) -> bool,
+{
+    let a_nodes: Vec<_> = a.nodes().iter().collect();
+    let b_nodes: Vec<_> = b.nodes().iter().collect();
+    let m = b_nodes.len();
+    let mut product = with_nodes::<H>(a_nodes.len() * m)?;
+
+    for (i, &u) in a_nodes.iter().enumerate() {
+        for (k, &x) in a_nodes.iter().enumerate().skip(i) {
+            let first = (u == x, u != x && a.has_edge(u, x));
+            for (j, &v) in b_nodes.iter().enumerate() {
+                for (l, &y) in b_nodes.iter().enumerate() {
+                    if (i * m + j) < (k * m + l) && adjacent(first, (v == y, v != y && b.has_edge(v, y))) {
+                        product.add_edge(i * m + j, k * m + l);
+                    }
+                }
+            }
+        }
+    }
+    Ok(product)
+}
+
+/// `(u, v)` is adjacent to `(x, y)` when one coordinate is equal and the
+/// other adjacent
+pub fn cartesian_product<A: Graph, B: Graph, H: Graph>(a: &A, b: &B) -> Result<H, TooManyNodes> {
+    product(a, b, |(ue, ua), (ve, va)| (ue && va) || (ua && ve))
+}
+
+/// `(u, v)` is adjacent to `(x, y)` when both coordinates are adjacent
+pub fn tensor_product<A: Graph, B: Graph, H: Graph>(a: &A, b: &B) -> Result<H, TooManyNodes> {
+    product(a, b, |(_, ua), (_, va)| ua && va)
+}
+
+/// The union of the cartesian and the tensor product
+pub fn strong_product<A: Graph, B: Graph, H: Graph>(a: &A, b: &B) -> Result<H, TooManyNodes> {
+    product(a, b, |(ue, ua), (ve, va)| (ue || ua) && (ve || va) && !(ue && ve))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32, Graph64};
+
+    fn cycle(n: usize) -> Graph16 {
+        crate::families::cycle(n).unwrap()
+    }
+
+    #[test]
+    fn complement_and_line_graph() {
+        assert_eq!(complement(&cycle(5)).to_canonical(), cycle(5).to_canonical());
+        assert_eq!(complement(&Graph16::complete(6)).edges_count(), 0);
+
+        let mut sparse = Graph16::empty();
+        sparse.add_node(3);
+        sparse.add_node(7);
+        assert!(complement(&sparse).has_edge(3, 7));
+
+        // The line graph of K4 is the octahedron
+        let line: Graph16 = line_graph(&Graph16::complete(4)).unwrap();
+        assert_eq!(line.nodes().count(), 6);
+        assert!(line.nodes().iter().all(|u| line.siblings(u).count() == 4));
+        let line: Graph16 = line_graph(&cycle(7)).unwrap();
+        assert_eq!(line.to_canonical(), cycle(7).to_canonical());
+
+        assert_eq!(line_graph::<_, Graph16>(&Graph16::complete(7)), Err(TooManyNodes { n: 21, maxn: 16 }));
+    }
+
+    #[test]
+    fn unions_and_joins() {
+        let union: Graph32 = disjoint_union(&cycle(5), &cycle(4)).unwrap();
+        assert_eq!(union.nodes().count(), 9);
+        assert_eq!(union.edges_count(), 9);
+        assert_eq!(union.components().count(), 2);
+        assert!(union.has_edge(5, 8));
+
+        assert_eq!(disjoint_union::<_, _, Graph16>(&cycle(8), &cycle(9)), Err(TooManyNodes { n: 17, maxn: 16 }));
+
+        // Gaps are kept, the offset only depends on the largest node of `a`
+        let mut gaps = Graph16::empty();
+        gaps.add_node(1);
+        gaps.add_node(3);
+        gaps.add_edge(1, 3);
+        let union: Graph16 = disjoint_union(&gaps, &gaps).unwrap();
+        assert_eq!(union.nodes().iter().collect::<Vec<_>>(), vec![1, 3, 5, 7]);
+        assert!(union.has_edge(5, 7));
+
+        // Joining a node to a cycle gives a wheel
+        let mut hub = Graph16::empty();
+        hub.add_node(0);
+        let wheel: Graph16 = join(&cycle(4), &hub).unwrap();
+        assert_eq!(wheel.edges_count(), 8);
+        assert_eq!(wheel.siblings(4).count(), 4);
+    }
+
+    #[test]
+    fn products() {
+        let k2 = Graph16::complete(2);
+        let square: Graph16 = cartesian_product(&k2, &k2).unwrap();
+        assert_eq!(square.to_canonical(), cycle(4).to_canonical());
+        let matching: Graph16 = tensor_product(&k2, &k2).unwrap();
+        assert_eq!(matching.edges_count(), 2);
+        let k4: Graph16 = strong_product(&k2, &k2).unwrap();
+        assert_eq!(k4, Graph16::complete(4));
+
+        for _ in 0..10 {
+            let a: Graph16 = crate::random::graph(6);
+            let b: Graph16 = crate::random::graph(7);
+            let (na, ma, nb, mb) = (6, a.edges_count(), 7, b.edges_count());
+            let cartesian: Graph64 = cartesian_product(&a, &b).unwrap();
+            let tensor: Graph64 = tensor_product(&a, &b).unwrap();
+            let strong: Graph64 = strong_product(&a, &b).unwrap();
+            assert_eq!(cartesian.edges_count(), na * mb + nb * ma);
+            assert_eq!(tensor.edges_count(), 2 * ma * mb);
+            assert_eq!(strong.edges_count(), cartesian.edges_count() + tensor.edges_count());
+        }
+
+        assert!(cartesian_product::<_, _, Graph32>(&cycle(6), &cycle(6)).is_err());
+    }
+}
@@ -1,96 +1,1105 @@
 use crate::graph::Graph;
 use crate::bitset::{Intset, Bitset};
+use crate::permutation::Permutation;
+use crate::embedding::{self, RotationSystem};
 
-pub fn from_graph6<G: Graph>(mut s: &str) -> G {
-    let n = if s.starts_with("~~") {
-        let r = s[2..8].as_bytes().iter().map(|b| b - b'?')
-            .fold(0, |acc, x| {
-                usize::from(x) + (acc << 6)
-            });
-
-        s = &s[8..];
-        r
-    } else if s.starts_with("~") {
-        let r = s[2..4].as_bytes().iter().map(|b| b - b'?')
-            .fold(0, |acc, x| {
-                usize::from(x) + (acc << 6)
-            });
-
-        s = &s[4..];
-        r
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// The input ended before the whole graph was read
+    UnexpectedEnd,
+    /// A byte that is not allowed at this position
+    InvalidByte(u8),
+    /// There is more input after the end of the graph
+    TrailingData,
+    /// The number of vertices could not be parsed
+    InvalidSize,
+    /// The graph has more vertices than the graph type can hold
+    TooManyVertices { n: usize, maxn: usize },
+    /// A rotation contains an invalid or duplicated neighbour, or an edge
+    /// that is missing from the rotation of the other endpoint
+    InvalidRotation,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Byte offset into the line
+    pub pos: usize,
+    /// Line number, starting at 1, if the graph was read from a file
+    pub line: Option<usize>,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, pos: usize) -> Self {
+        Self {
+            kind,
+            pos,
+            line: None,
+        }
+    }
+
+    pub fn with_line(self, line: usize) -> Self {
+        Self {
+            line: Some(line),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}, ", line)?;
+        }
+        write!(f, "byte {}: ", self.pos)?;
+        match self.kind {
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseErrorKind::InvalidByte(b) => write!(f, "invalid byte {:?}", char::from(b)),
+            ParseErrorKind::TrailingData => write!(f, "trailing data after graph"),
+            ParseErrorKind::InvalidSize => write!(f, "invalid number of vertices"),
+            ParseErrorKind::TooManyVertices { n, maxn } =>
+                write!(f, "graph has {} vertices, graph type holds at most {}", n, maxn),
+            ParseErrorKind::InvalidRotation => write!(f, "invalid rotation"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn check_vertices<G: Graph>(n: usize, pos: usize) -> Result<(), ParseError> {
+    if n > G::MAXN {
+        Err(ParseError::new(ParseErrorKind::TooManyVertices { n, maxn: G::MAXN }, pos))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that all bytes are in the printable range used by graph6 and friends
+fn check_bytes(s: &[u8], offset: usize) -> Result<(), ParseError> {
+    match s.iter().position(|b| !(63..=126).contains(b)) {
+        Some(i) => Err(ParseError::new(ParseErrorKind::InvalidByte(s[i]), offset + i)),
+        None => Ok(()),
+    }
+}
+
+fn check_length(s: &[u8], offset: usize, len: usize) -> Result<(), ParseError> {
+    check_bytes(s, offset)?;
+    if s.len() < len {
+        Err(ParseError::new(ParseErrorKind::UnexpectedEnd, offset + s.len()))
+    } else if s.len() > len {
+        Err(ParseError::new(ParseErrorKind::TrailingData, offset + len))
+    } else {
+        Ok(())
+    }
+}
+
+fn strip_marker(s: &[u8], marker: u8) -> Result<&[u8], ParseError> {
+    match s.first() {
+        Some(b) if *b == marker => Ok(&s[1..]),
+        Some(b) => Err(ParseError::new(ParseErrorKind::InvalidByte(*b), 0)),
+        None => Err(ParseError::new(ParseErrorKind::UnexpectedEnd, 0)),
+    }
+}
+
+fn graph_size(s: &[u8], offset: usize) -> Result<(usize, &[u8]), ParseError> {
+    let len = if s.starts_with(b"~~") {
+        8
+    } else if s.starts_with(b"~") {
+        4
     } else {
-        let r = usize::from(s.as_bytes()[0] - b'?');
-        s = &s[1..];
-        r
+        1
     };
 
+    if s.len() < len {
+        return Err(ParseError::new(ParseErrorKind::UnexpectedEnd, offset + s.len()))
+    }
+    check_bytes(&s[..len], offset)?;
+
+    let digits = match len {
+        8 => &s[2..8],
+        4 => &s[1..4],
+        _ => &s[..1],
+    };
+    let n = digits.iter().map(|b| b - b'?')
+        .fold(0, |acc, x| {
+            usize::from(x) + (acc << 6)
+        });
+
+    Ok((n, &s[len..]))
+}
+
+fn push_graph_size(res: &mut String, n: usize) {
+    let bytes = if n < 63 {
+        0
+    } else if n < 258048 {
+        res.push('~');
+        3
+    } else {
+        res.push_str("~~");
+        6
+    };
+
+    if bytes == 0 {
+        res.push((n as u8 + b'?') as char);
+    } else {
+        for i in (0..bytes).rev() {
+            res.push((((n >> (6*i)) & 0x3f) as u8 + b'?') as char);
+        }
+    }
+}
+
+struct Bits<'a> {
+    bytes: &'a [u8],
+    k: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            k: 6,
+        }
+    }
+
+    fn read(&mut self, n: usize) -> Option<usize> {
+        let mut x = 0;
+        for _ in 0..n {
+            x = (x << 1) | usize::from(self.next()?);
+        }
+        Some(x)
+    }
+}
+
+impl<'a> Iterator for Bits<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let b = *self.bytes.first()? - b'?';
+        self.k -= 1;
+        let bit = (b >> self.k) & 1;
+        if self.k == 0 {
+            self.bytes = &self.bytes[1..];
+            self.k = 6;
+        }
+        Some(bit)
+    }
+}
+
+struct BitWriter<'a> {
+    res: &'a mut String,
+    x: u8,
+    k: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(res: &'a mut String) -> Self {
+        Self {
+            res,
+            x: 0,
+            k: 6,
+        }
+    }
+
+    fn push(&mut self, bit: bool) {
+        self.x = (self.x << 1) | u8::from(bit);
+        self.k -= 1;
+        if self.k == 0 {
+            self.res.push((self.x + b'?') as char);
+            self.x = 0;
+            self.k = 6;
+        }
+    }
+
+    fn push_bits(&mut self, x: usize, n: usize) {
+        for i in (0..n).rev() {
+            self.push((x >> i) & 1 == 1);
+        }
+    }
+
+    /// Number of bits needed to fill the current byte
+    fn remaining(&self) -> usize {
+        self.k % 6
+    }
+
+    fn finish(mut self) {
+        while self.remaining() > 0 {
+            self.push(false);
+        }
+    }
+}
+
+pub fn from_graph6<G: Graph>(s: &str) -> Result<G, ParseError> {
+    let input = s.trim_end().as_bytes();
+    let (n, body) = graph_size(input, 0)?;
+    check_vertices::<G>(n, 0)?;
+    check_length(body, input.len() - body.len(), (n * n.saturating_sub(1) / 2).div_ceil(6))?;
+
     let mut graph = G::empty();
     for i in 0..n {
         graph.add_node(i);
     }
 
-    let mut bits = s.as_bytes().iter().map(|b| b - b'?')
-        .flat_map(|b| std::array::IntoIter::new([(b >> 5) & 1, (b >> 4) & 1, (b >> 3) & 1, (b >> 2) & 1, (b >> 1) & 1, b & 1]));
+    let mut bits = Bits::new(body);
 
     for u in 1..n {
         for v in 0..u {
-            if bits.next().unwrap() == 1 {
+            if bits.next() == Some(1) {
                 graph.add_edge(u, v);
             }
         }
     }
 
-    graph
+    Ok(graph)
+}
+
+/// The relabeling used when writing `graph` in graph6, sparse6 or digraph6.
+/// The nodes of `graph` are mapped to `0..n` in increasing order and the unused
+/// labels to `n..G::MAXN`, so reading back the written string gives `graph`
+/// shuffled by this permutation.
+pub fn relabeling<G: Graph>(graph: &G) -> G::Perm {
+    let nodes = graph.nodes();
+    let unused = (0..G::MAXN).filter(|u| !nodes.get(*u));
+    G::Perm::from_iter(nodes.iter().chain(unused).enumerate().map(|(i, u)| (u, i)))
+        .unwrap()
 }
 
+/// Writes `graph` in graph6 format. The nodes are relabeled according to
+/// `relabeling`, a graph with no gaps in its node set is written unchanged.
 pub fn to_graph6<G: Graph>(graph: &G) -> String {
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+
     let mut res = String::new();
-    res.push((graph.nodes().count() as u8 + b'?') as char);
-    let mut bits = 0;
-    let mut j = 0;
-    for (i, u) in graph.nodes().iter().enumerate().skip(1) {
-        for v in graph.nodes().iter().take(i) {
-            bits = bits << 1;
-            if graph.has_edge(u, v) {
-                bits |= 1;
+    push_graph_size(&mut res, nodes.len());
+
+    let mut bits = BitWriter::new(&mut res);
+    for (i, u) in nodes.iter().enumerate() {
+        for v in &nodes[..i] {
+            bits.push(graph.has_edge(*u, *v));
+        }
+    }
+    bits.finish();
+
+    res
+}
+
+/// Number of bits used to encode a vertex in sparse6
+fn sparse6_width(n: usize) -> usize {
+    (usize::BITS - n.saturating_sub(1).leading_zeros()) as usize
+}
+
+pub fn from_sparse6<G: Graph>(s: &str) -> Result<G, ParseError> {
+    let input = s.trim_end().as_bytes();
+    let (n, body) = graph_size(strip_marker(input, b':')?, 1)?;
+    check_vertices::<G>(n, 1)?;
+    check_bytes(body, input.len() - body.len())?;
+    let k = sparse6_width(n);
+
+    let mut graph = G::empty();
+    for i in 0..n {
+        graph.add_node(i);
+    }
+
+    let mut bits = Bits::new(body);
+    let mut v = 0;
+
+    while let Some(b) = bits.next() {
+        if b == 1 {
+            v += 1;
+        }
+        let x = if let Some(x) = bits.read(k) {
+            x
+        } else {
+            // Incomplete pair at the end is padding
+            break
+        };
+        if x > v {
+            v = x;
+        } else if v < n && x != v {
+            graph.add_edge(x, v);
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Writes `graph` in sparse6 format. The nodes are relabeled according to
+/// `relabeling`.
+pub fn to_sparse6<G: Graph>(graph: &G) -> String {
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+    let n = nodes.len();
+    let k = sparse6_width(n);
+
+    let mut res = String::from(":");
+    push_graph_size(&mut res, n);
+
+    let mut bits = BitWriter::new(&mut res);
+    let mut v = 0;
+
+    for j in 0..n {
+        for i in 0..j {
+            if !graph.has_edge(nodes[j], nodes[i]) {
+                continue
             }
-            if j % 6 == 5 {
-                res.push((bits + b'?') as char);
-                bits = 0;
+            if j == v {
+                bits.push(false);
+            } else {
+                bits.push(true);
+                if j > v + 1 {
+                    bits.push_bits(j, k);
+                    bits.push(false);
+                }
+                v = j;
             }
-            j += 1;
+            bits.push_bits(i, k);
         }
     }
-    if j % 6 != 0 {
-        bits = bits << (6 - j % 6);
-        res.push((bits + b'?') as char);
+
+    let pad = bits.remaining();
+    if pad > 0 {
+        // Padding with ones could be read as an extra edge to the last node,
+        // nauty avoids this by starting the padding with a zero.
+        if pad > k && n == (1 << k) && v + 2 == n {
+            bits.push(false);
+        }
+        while bits.remaining() > 0 {
+            bits.push(true);
+        }
     }
+
     res
 }
 
-pub fn from_upper_tri<G: Graph>(mut s: &str) -> Option<G> {
-    s = s.trim();
-    let sn = s.split(' ').next().unwrap();
-    let n = sn.parse::<usize>().unwrap();
-    if n > G::MAXN {
-        return None
+/// Reads a digraph6 string. Since `G` is undirected an edge is added for every
+/// arc in either direction and loops are ignored.
+pub fn from_digraph6<G: Graph>(s: &str) -> Result<G, ParseError> {
+    let input = s.trim_end().as_bytes();
+    let (n, body) = graph_size(strip_marker(input, b'&')?, 1)?;
+    check_vertices::<G>(n, 1)?;
+    check_length(body, input.len() - body.len(), (n * n).div_ceil(6))?;
+
+    let mut graph = G::empty();
+    for i in 0..n {
+        graph.add_node(i);
     }
-    s = s.strip_prefix(sn).unwrap();
+
+    let mut bits = Bits::new(body);
+
+    for u in 0..n {
+        for v in 0..n {
+            if bits.next() == Some(1) && u != v {
+                graph.add_edge(u, v);
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Writes `graph` in digraph6 format with an arc in both directions for every
+/// edge. The nodes are relabeled according to `relabeling`.
+pub fn to_digraph6<G: Graph>(graph: &G) -> String {
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+
+    let mut res = String::from("&");
+    push_graph_size(&mut res, nodes.len());
+
+    let mut bits = BitWriter::new(&mut res);
+    for u in &nodes {
+        for v in &nodes {
+            bits.push(u != v && graph.has_edge(*u, *v));
+        }
+    }
+    bits.finish();
+
+    res
+}
+
+pub fn from_upper_tri<G: Graph>(s: &str) -> Result<G, ParseError> {
+    let input = s.as_bytes();
+    let start = input.iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(input.len());
+    let end = input.iter().skip(start)
+        .position(|b| b.is_ascii_whitespace())
+        .map_or(input.len(), |i| start + i);
+
+    let n = s[start..end].parse::<usize>()
+        .map_err(|_| ParseError::new(ParseErrorKind::InvalidSize, start))?;
+    check_vertices::<G>(n, start)?;
 
     let mut graph = G::empty();
     for i in 0..n {
         graph.add_node(i);
     }
 
-    let mut edges = s.chars().filter(|c| *c == '1' || *c == '0');
+    let mut edges = input.iter().enumerate().skip(end)
+        .filter(|(_, b)| !b.is_ascii_whitespace());
 
-    for u in 0..n - 1 {
+    for u in 0..n {
         for v in u+1..n {
-            if edges.next().unwrap() == '1' {
-                graph.add_edge(u, v);
+            match edges.next() {
+                Some((_, b'1')) => graph.add_edge(u, v),
+                Some((_, b'0')) => {},
+                Some((i, b)) => return Err(ParseError::new(ParseErrorKind::InvalidByte(*b), i)),
+                None => return Err(ParseError::new(ParseErrorKind::UnexpectedEnd, input.len())),
             }
         }
     }
-    assert!(edges.next().is_none());
-    Some(graph)
+
+    if let Some((i, _)) = edges.next() {
+        return Err(ParseError::new(ParseErrorKind::TrailingData, i))
+    }
+    Ok(graph)
+}
+
+/// Writes `graph` in upper triangle format, the number of nodes followed by
+/// the upper triangle of the adjacency matrix row by row. The nodes are
+/// relabeled according to `relabeling`.
+pub fn to_upper_tri<G: Graph>(graph: &G) -> String {
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+
+    let mut res = format!("{} ", nodes.len());
+    for (i, u) in nodes.iter().enumerate() {
+        for v in &nodes[i+1..] {
+            res.push(if graph.has_edge(*u, *v) { '1' } else { '0' });
+        }
+    }
+
+    res
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Format {
+    Graph6,
+    Sparse6,
+    Digraph6,
+    UpperTri,
+}
+
+impl Format {
+    /// Guesses the format of a single line. Upper triangle lines start with a
+    /// digit which is never a valid graph6 byte.
+    pub fn detect(line: &str) -> Format {
+        match line.trim_start().as_bytes().first() {
+            Some(b':') => Format::Sparse6,
+            Some(b'&') => Format::Digraph6,
+            Some(b) if b.is_ascii_digit() => Format::UpperTri,
+            _ => Format::Graph6,
+        }
+    }
+
+    /// The `>>graph6<<` style header nauty writes in front of the first graph
+    pub fn header(&self) -> Option<&'static str> {
+        match self {
+            Format::Graph6 => Some(">>graph6<<"),
+            Format::Sparse6 => Some(">>sparse6<<"),
+            Format::Digraph6 => Some(">>digraph6<<"),
+            Format::UpperTri => None,
+        }
+    }
+
+    /// Strips a nauty header from the start of `line`
+    fn strip_header(line: &str) -> Option<(Format, &str)> {
+        [Format::Graph6, Format::Sparse6, Format::Digraph6].iter()
+            .find_map(|format| {
+                line.strip_prefix(format.header()?).map(|rest| (*format, rest))
+            })
+    }
+
+    pub fn parse<G: Graph>(&self, s: &str) -> Result<G, ParseError> {
+        match self {
+            Format::Graph6 => from_graph6(s),
+            Format::Sparse6 => from_sparse6(s),
+            Format::Digraph6 => from_digraph6(s),
+            Format::UpperTri => from_upper_tri(s),
+        }
+    }
+
+    pub fn write<G: Graph>(&self, graph: &G) -> String {
+        match self {
+            Format::Graph6 => to_graph6(graph),
+            Format::Sparse6 => to_sparse6(graph),
+            Format::Digraph6 => to_digraph6(graph),
+            Format::UpperTri => to_upper_tri(graph),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "{}", err),
+            ReadError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            ReadError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(err: std::io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(err: ParseError) -> Self {
+        ReadError::Parse(err)
+    }
+}
+
+/// Reads one graph per line. The format is detected for every line unless it
+/// is fixed with `with_format` or by a nauty header such as `>>sparse6<<`.
+/// Empty lines are skipped.
+pub struct GraphReader<R, G> {
+    reader: R,
+    format: Option<Format>,
+    line: usize,
+    buf: String,
+    _marker: std::marker::PhantomData<G>,
+}
+
+impl<R: std::io::BufRead, G: Graph> GraphReader<R, G> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            format: None,
+            line: 0,
+            buf: String::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn with_format(reader: R, format: Format) -> Self {
+        Self {
+            format: Some(format),
+            ..Self::new(reader)
+        }
+    }
+
+    /// Number of lines read so far
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    fn read_graph(&mut self) -> Result<Option<G>, ReadError> {
+        loop {
+            self.buf.clear();
+            if self.reader.read_line(&mut self.buf)? == 0 {
+                return Ok(None)
+            }
+            self.line += 1;
+
+            let mut line = self.buf.trim_end();
+            let mut offset = 0;
+            if let Some((format, rest)) = Format::strip_header(line) {
+                self.format = Some(format);
+                offset = line.len() - rest.len();
+                line = rest;
+            }
+            if line.trim().is_empty() {
+                continue
+            }
+
+            let format = self.format.unwrap_or_else(|| Format::detect(line));
+            return format.parse(line)
+                .map(Some)
+                .map_err(|e| ParseError {
+                    pos: e.pos + offset,
+                    ..e.with_line(self.line)
+                }.into())
+        }
+    }
+}
+
+impl<R: std::io::BufRead, G: Graph> Iterator for GraphReader<R, G> {
+    type Item = Result<G, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_graph().transpose()
+    }
+}
+
+/// Writes one graph per line in the given format
+pub struct GraphWriter<W> {
+    writer: W,
+    format: Format,
+    header: bool,
+}
+
+impl<W: std::io::Write> GraphWriter<W> {
+    pub fn new(writer: W, format: Format) -> Self {
+        Self {
+            writer,
+            format,
+            header: false,
+        }
+    }
+
+    /// Writes a nauty header such as `>>graph6<<` in front of the first graph
+    pub fn with_header(writer: W, format: Format) -> Self {
+        Self {
+            header: format.header().is_some(),
+            ..Self::new(writer, format)
+        }
+    }
+
+    pub fn write<G: Graph>(&mut self, graph: &G) -> std::io::Result<()> {
+        if self.header {
+            self.header = false;
+            write!(self.writer, "{}", self.format.header().unwrap_or(""))?;
+        }
+        writeln!(self.writer, "{}", self.format.write(graph))
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// The rotations of `embedding` with the nodes relabeled according to
+/// `relabeling`. Each rotation starts at the smallest neighbour and follows
+/// `RotationSystem::after`.
+fn rotations<G: Graph, R: RotationSystem<G>>(embedding: &R) -> Vec<Vec<usize>> {
+    let graph = embedding.to_graph();
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+    let mut labels = vec![0; G::MAXN];
+    for (i, u) in nodes.iter().enumerate() {
+        labels[*u] = i;
+    }
+
+    nodes.iter().map(|u| {
+        let mut rotation = Vec::new();
+        if let Some(v0) = graph.siblings(*u).smallest() {
+            let mut v = v0;
+            loop {
+                rotation.push(labels[v]);
+                v = embedding.after(*u, v);
+                if v == v0 {
+                    break
+                }
+            }
+        }
+        rotation
+    }).collect()
+}
+
+pub const PLANAR_CODE_HEADER: &str = ">>planar_code<<";
+
+/// Writes embeddings in plantri's planar_code format, including the header.
+/// Rotations are written in the order of `RotationSystem::after` which
+/// planar_code reads as clockwise.
+pub fn to_planar_code<G: Graph, R: RotationSystem<G>>(embeddings: &[R]) -> Vec<u8> {
+    let rotations: Vec<_> = embeddings.iter().map(rotations).collect();
+    let wide = rotations.iter().any(|r| r.len() > 255);
+
+    let mut res = Vec::new();
+    if wide {
+        res.extend_from_slice(b">>planar_code be<<");
+    } else {
+        res.extend_from_slice(PLANAR_CODE_HEADER.as_bytes());
+    }
+
+    let push = |res: &mut Vec<u8>, x: usize| if wide {
+        res.extend_from_slice(&(x as u16).to_be_bytes());
+    } else {
+        res.push(x as u8);
+    };
+
+    for rotation in &rotations {
+        if wide {
+            res.push(0);
+        }
+        push(&mut res, rotation.len());
+        for vs in rotation {
+            for v in vs {
+                push(&mut res, v + 1);
+            }
+            push(&mut res, 0);
+        }
+    }
+
+    res
+}
+
+struct PlanarCode<'a> {
+    data: &'a [u8],
+    i: usize,
+    wide: bool,
+    little_endian: bool,
+}
+
+impl<'a> PlanarCode<'a> {
+    fn next(&mut self) -> Result<usize, ParseError> {
+        let len = if self.wide { 2 } else { 1 };
+        let bytes = self.data.get(self.i..self.i+len)
+            .ok_or(ParseError::new(ParseErrorKind::UnexpectedEnd, self.data.len()))?;
+        self.i += len;
+
+        Ok(match bytes {
+            [b] => usize::from(*b),
+            [a, b] if self.little_endian => usize::from(u16::from_le_bytes([*a, *b])),
+            [a, b] => usize::from(u16::from_be_bytes([*a, *b])),
+            _ => unreachable!(),
+        })
+    }
+}
+
+/// Reads all embeddings from planar_code data. The header is optional, the
+/// `le` and `be` variants are supported for graphs with more than 255 nodes.
+pub fn from_planar_code<G: Graph>(data: &[u8]) -> Result<Vec<G::Embedding>, ParseError> {
+    let mut code = PlanarCode {
+        data,
+        i: 0,
+        wide: false,
+        little_endian: false,
+    };
+
+    let headers: [(&[u8], bool); 3] = [
+        (PLANAR_CODE_HEADER.as_bytes(), false),
+        (b">>planar_code le<<", true),
+        (b">>planar_code be<<", false),
+    ];
+    for (header, little_endian) in &headers {
+        if data.starts_with(header) {
+            code.i = header.len();
+            code.little_endian = *little_endian;
+        }
+    }
+
+    let mut embeddings = Vec::new();
+    while code.i < data.len() {
+        let start = code.i;
+        code.wide = data[code.i] == 0;
+        if code.wide {
+            code.i += 1;
+        }
+
+        let n = code.next()?;
+        check_vertices::<G>(n, start)?;
+
+        let mut rotations: Vec<_> = (0..n).map(|u| (u, Vec::new())).collect();
+        let mut pos = vec![0; n];
+        for (u, (_, rotation)) in rotations.iter_mut().enumerate() {
+            pos[u] = code.i;
+            loop {
+                match code.next()? {
+                    0 => break,
+                    v => rotation.push(v - 1),
+                }
+            }
+        }
+        embeddings.push(embedding::from_rotations::<G>(&rotations)
+            .map_err(|u| ParseError::new(ParseErrorKind::InvalidRotation, pos[u]))?);
+    }
+
+    Ok(embeddings)
+}
+
+/// Writes an embedding in the embed text format. The first line is the number
+/// of nodes followed by one line `u: v1 v2 ...` with the rotation of each node,
+/// the nodes are relabeled according to `relabeling`.
+pub fn to_embed<G: Graph, R: RotationSystem<G>>(embedding: &R) -> String {
+    use std::fmt::Write;
+
+    let rotations = rotations(embedding);
+    let mut res = String::new();
+    writeln!(res, "{}", rotations.len()).unwrap();
+    for (u, rotation) in rotations.iter().enumerate() {
+        write!(res, "{}:", u).unwrap();
+        for v in rotation {
+            write!(res, " {}", v).unwrap();
+        }
+        res.push('\n');
+    }
+    res
+}
+
+/// Reads all embeddings in embed text format, embeddings may be separated by
+/// empty lines.
+pub fn from_embed<G: Graph>(s: &str) -> Result<Vec<G::Embedding>, ParseError> {
+    let mut lines = s.lines().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let mut embeddings = Vec::new();
+    while let Some((i, line)) = lines.next() {
+        let n = line.trim().parse::<usize>()
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidSize, 0).with_line(i+1))?;
+        check_vertices::<G>(n, 0).map_err(|e| e.with_line(i+1))?;
+
+        let mut rotations = Vec::new();
+        let mut rotation_lines = Vec::new();
+        for u in 0..n {
+            let (i, line) = lines.next()
+                .ok_or_else(|| ParseError::new(ParseErrorKind::UnexpectedEnd, 0).with_line(i+1))?;
+            let error = |kind, pos| ParseError::new(kind, pos).with_line(i+1);
+
+            let (label, rotation) = line.split_once(':')
+                .ok_or_else(|| error(ParseErrorKind::UnexpectedEnd, line.len()))?;
+            if label.trim().parse::<usize>() != Ok(u) {
+                return Err(error(ParseErrorKind::InvalidRotation, 0))
+            }
+
+            rotations.push((u, rotation.split_whitespace()
+                .map(|v| v.parse::<usize>()
+                    .map_err(|_| error(ParseErrorKind::InvalidRotation, label.len() + 1)))
+                .collect::<Result<Vec<_>, _>>()?));
+            rotation_lines.push(i);
+        }
+
+        embeddings.push(embedding::from_rotations::<G>(&rotations)
+            .map_err(|u| {
+                ParseError::new(ParseErrorKind::InvalidRotation, 0).with_line(rotation_lines[u] + 1)
+            })?);
+    }
+
+    Ok(embeddings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph64};
+    use crate::{planar, toroidal};
+
+    fn edges<G: Graph>(graph: &G) -> Vec<(usize, usize)> {
+        let mut edges: Vec<_> = graph.edges().map(|(u, v)| (v, u)).collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn graph6_example() {
+        let graph: Graph16 = from_graph6("DQc").unwrap();
+        assert_eq!(graph.nodes().count(), 5);
+        assert_eq!(edges(&graph), vec![(0, 2), (0, 4), (1, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn sparse6_example() {
+        let graph: Graph16 = from_sparse6(":Fa@x^").unwrap();
+        assert_eq!(graph.nodes().count(), 7);
+        assert_eq!(edges(&graph), vec![(0, 1), (0, 2), (1, 2), (5, 6)]);
+        assert_eq!(to_sparse6(&graph), ":Fa@x^");
+    }
+
+    #[test]
+    fn sparse6_padding() {
+        // n = 2^k with the last edge ending at n-2 needs the special padding
+        let mut graph = Graph16::empty();
+        for i in 0..4 {
+            graph.add_node(i);
+        }
+        graph.add_edge(0, 2);
+        let s = to_sparse6(&graph);
+        assert_eq!(edges(&from_sparse6::<Graph16>(&s).unwrap()), vec![(0, 2)]);
+
+        let k4: Graph16 = Graph16::complete(4);
+        assert_eq!(from_sparse6::<Graph16>(&to_sparse6(&k4)).unwrap(), k4);
+    }
+
+    #[test]
+    fn digraph6_example() {
+        let graph: Graph16 = from_digraph6("&DI?AO?").unwrap();
+        assert_eq!(graph.nodes().count(), 5);
+        assert_eq!(edges(&graph), vec![(0, 2), (0, 4), (1, 3), (3, 4)]);
+        assert_eq!(from_digraph6::<Graph16>(&to_digraph6(&graph)).unwrap(), graph);
+    }
+
+    #[test]
+    fn round_trip_random() {
+        for n in 1..=64 {
+            let graph: Graph64 = crate::random::graph(n);
+            assert_eq!(from_graph6::<Graph64>(&to_graph6(&graph)).unwrap(), graph);
+            assert_eq!(from_sparse6::<Graph64>(&to_sparse6(&graph)).unwrap(), graph);
+            assert_eq!(from_digraph6::<Graph64>(&to_digraph6(&graph)).unwrap(), graph);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        use ParseErrorKind::*;
+        let kind = |r: Result<Graph16, ParseError>| r.map_err(|e| (e.kind, e.pos)).err();
+
+        assert_eq!(kind(from_graph6("")), Some((UnexpectedEnd, 0)));
+        assert_eq!(kind(from_graph6("DQ")), Some((UnexpectedEnd, 2)));
+        assert_eq!(kind(from_graph6("DQcc")), Some((TrailingData, 3)));
+        assert_eq!(kind(from_graph6("DQ c")), Some((InvalidByte(b' '), 2)));
+        assert_eq!(kind(from_graph6("Q")), Some((TooManyVertices { n: 18, maxn: 16 }, 0)));
+        assert_eq!(kind(from_sparse6("Fa@x^")), Some((InvalidByte(b'F'), 0)));
+        assert_eq!(kind(from_digraph6("&DI?A")), Some((UnexpectedEnd, 5)));
+        assert_eq!(kind(from_upper_tri("3 11")), Some((UnexpectedEnd, 4)));
+        assert_eq!(kind(from_upper_tri("3 1101")), Some((TrailingData, 5)));
+        assert_eq!(kind(from_upper_tri("3 1x1")), Some((InvalidByte(b'x'), 3)));
+        assert_eq!(kind(from_upper_tri("a 111")), Some((InvalidSize, 0)));
+        assert_eq!(kind(from_upper_tri("17 1")), Some((TooManyVertices { n: 17, maxn: 16 }, 0)));
+
+        let err = from_graph6::<Graph16>("DQ").unwrap_err().with_line(3);
+        assert_eq!(err.to_string(), "line 3, byte 2: unexpected end of input");
+    }
+
+    #[test]
+    fn graph6_long_header() {
+        let graph: Graph64 = Graph64::complete(63);
+        assert!(to_graph6(&graph).starts_with("~??~"));
+        let graph: Graph64 = Graph64::complete(64);
+        assert!(to_graph6(&graph).starts_with("~?@?"));
+        assert_eq!(from_graph6::<Graph64>(&to_graph6(&graph)).unwrap(), graph);
+    }
+
+    #[test]
+    fn graph6_relabeling() {
+        let mut graph = Graph16::empty();
+        for u in &[1, 4, 5, 9, 15] {
+            graph.add_node(*u);
+        }
+        graph.add_edge(1, 9);
+        graph.add_edge(4, 15);
+        let perm = relabeling(&graph);
+        assert_eq!(perm.get(9), 3);
+        assert_eq!(perm.get(15), 4);
+
+        let read: Graph16 = from_graph6(&to_graph6(&graph)).unwrap();
+        graph.shuffle(&perm);
+        assert_eq!(read, graph);
+        assert_eq!(edges(&read), vec![(0, 3), (1, 4)]);
+    }
+
+    #[test]
+    fn upper_tri_round_trip() {
+        for n in 1..=16 {
+            let graph: Graph16 = crate::random::graph(n);
+            assert_eq!(from_upper_tri::<Graph16>(&to_upper_tri(&graph)).unwrap(), graph);
+        }
+    }
+
+    #[test]
+    fn reader_detects_format() {
+        let input = "DQc\n:Fa@x^\n\n3 110\n&DI?AO?\n";
+        let graphs = GraphReader::<_, Graph16>::new(input.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(graphs.len(), 4);
+        assert_eq!(graphs[0], graphs[3]);
+        assert_eq!(edges(&graphs[1]), vec![(0, 1), (0, 2), (1, 2), (5, 6)]);
+        assert_eq!(edges(&graphs[2]), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn reader_header_and_errors() {
+        let input = ">>sparse6<<:Fa@x^\n:Fa@x^\nDQc\n";
+        let mut reader = GraphReader::<_, Graph16>::new(input.as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next().unwrap() {
+            Err(ReadError::Parse(err)) => {
+                assert_eq!(err.line, Some(3));
+                assert_eq!(err.kind, ParseErrorKind::InvalidByte(b'D'));
+            }
+            _ => panic!("graph6 line should not parse as sparse6"),
+        }
+        assert!(reader.next().is_none());
+
+        let mut reader = GraphReader::<_, Graph16>::new(">>graph6<<DQ\n".as_bytes());
+        match reader.next().unwrap() {
+            Err(ReadError::Parse(err)) => assert_eq!((err.line, err.pos), (Some(1), 12)),
+            _ => panic!("truncated graph6 should not parse"),
+        }
+    }
+
+    #[test]
+    fn writer_round_trip() {
+        let graphs: Vec<Graph64> = (1..=64).map(crate::random::graph).collect();
+        for format in &[Format::Graph6, Format::Sparse6, Format::Digraph6, Format::UpperTri] {
+            let mut writer = GraphWriter::with_header(Vec::new(), *format);
+            for graph in &graphs {
+                writer.write(graph).unwrap();
+            }
+            let output = writer.into_inner();
+            let read = GraphReader::<_, Graph64>::new(&output[..])
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(read, graphs);
+        }
+    }
+
+    #[test]
+    fn planar_code_round_trip() {
+        let k4 = planar::fastdmp(&Graph16::complete(4)).unwrap();
+        let k7 = toroidal::find_embedding(&Graph16::complete(7)).unwrap();
+
+        let data = to_planar_code(&[k4, k7]);
+        assert!(data.starts_with(PLANAR_CODE_HEADER.as_bytes()));
+        assert_eq!(&data[PLANAR_CODE_HEADER.len()..][..17],
+                   &[4, 2, 3, 4, 0, 1, 4, 3, 0, 1, 2, 4, 0, 1, 3, 2, 0]);
+
+        let embeddings = from_planar_code::<Graph16>(&data).unwrap();
+        assert_eq!(embeddings.len(), 2);
+        for (a, b) in embeddings.iter().zip(&[k4, k7]) {
+            assert_eq!(a.genus(), b.genus());
+            assert_eq!(edges(&a.to_graph()), edges(&b.to_graph()));
+            assert_eq!(rotations(a), rotations(b));
+        }
+    }
+
+    #[test]
+    fn embed_round_trip() {
+        for _ in 0..100 {
+            let graph: Graph16 = crate::random::graph(8);
+            let embedding = match planar::find_embedding(&graph) {
+                Some(embedding) => embedding,
+                None => continue,
+            };
+
+            let s = to_embed(&embedding);
+            let parsed = from_embed::<Graph16>(&format!("{}\n{}", s, s)).unwrap();
+            assert_eq!(parsed.len(), 2);
+            assert_eq!(parsed[0].genus(), 0);
+            assert_eq!(rotations(&parsed[1]), rotations(&embedding));
+        }
+    }
+
+    #[test]
+    fn rotation_errors() {
+        let err = from_embed::<Graph16>("3\n0: 1 2\n1: 0 2\n2: 0\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidRotation);
+        assert_eq!(err.line, Some(4));
+
+        // Node 1 does not list node 2 in its rotation
+        let err = from_embed::<Graph16>("3\n0: 2\n1:\n2: 1\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidRotation);
+        assert_eq!(err.line, Some(3));
+
+        let err = from_embed::<Graph16>("3\n0: 1 2 1\n1: 0\n2: 0\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidRotation);
+        assert_eq!(err.line, Some(2));
+
+        let err = from_embed::<Graph16>("2\n0: 1\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd);
+
+        let err = from_planar_code::<Graph16>(&[2, 2, 0, 2, 0]).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidRotation);
+        assert_eq!(err.pos, 3);
+
+        let err = from_planar_code::<Graph16>(&[2, 2, 2, 0, 1, 0]).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidRotation);
+        assert_eq!(err.pos, 1);
+    }
 }
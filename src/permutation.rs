@@ -1,6 +1,7 @@
 pub type Perm16 = SmallPerm<16>;
 pub type Perm32 = SmallPerm<32>;
 pub type Perm64 = SmallPerm<64>;
+pub type Perm128 = SmallPerm<128>;
 
 pub trait Permutation: Clone + Eq + std::hash::Hash {
     fn new() -> Self;
@@ -112,3 +113,92 @@ impl<'a, P: Permutation> Iterator for PermIter<'a, P> {
         }
     }
 }
+
+/// Heap allocated permutation, points after the stored ones are fixed.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DynPerm {
+    perm: Vec<usize>,
+}
+
+impl DynPerm {
+    fn normalize(&mut self) {
+        while let Some(j) = self.perm.last() {
+            if *j + 1 != self.perm.len() {
+                break
+            }
+            self.perm.pop();
+        }
+    }
+}
+
+impl Permutation for DynPerm {
+    fn new() -> Self {
+        Self {
+            perm: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.perm.len()
+    }
+
+    fn get(&self, i: usize) -> usize {
+        self.perm.get(i).copied().unwrap_or(i)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        let n = std::cmp::max(i, j) + 1;
+        while self.perm.len() < n {
+            self.perm.push(self.perm.len());
+        }
+        self.perm.swap(i, j);
+        self.normalize();
+    }
+
+    fn from_iter<I: Iterator<Item = (usize, usize)>>(iter: I) -> Option<Self> {
+        let mut perm = Vec::new();
+        for (i, j) in iter {
+            let n = std::cmp::max(i, j) + 1;
+            if perm.len() < n {
+                perm.resize(n, None);
+            }
+            perm[i] = Some(j);
+        }
+
+        let perm: Vec<usize> = perm.into_iter().enumerate()
+            .map(|(i, j)| j.unwrap_or(i))
+            .collect();
+        let mut used = vec![false; perm.len()];
+        for j in &perm {
+            if used[*j] {
+                return None
+            }
+            used[*j] = true;
+        }
+
+        let mut res = Self {
+            perm
+        };
+        res.normalize();
+        Some(res)
+    }
+
+    fn invert(&self) -> Self {
+        let mut perm = vec![0; self.perm.len()];
+        for (i, j) in self.perm.iter().enumerate() {
+            perm[*j] = i;
+        }
+        Self {
+            perm
+        }
+    }
+
+    fn chain(&self, other: &Self) -> Self {
+        let n = std::cmp::max(self.perm.len(), other.perm.len());
+        let mut res = Self {
+            perm: (0..n).map(|i| other.get(self.get(i))).collect(),
+        };
+        res.normalize();
+        res
+    }
+}
@@ -250,3 +250,95 @@ impl<const N: usize> std::fmt::Debug for SmallSeq<N> {
         f.debug_list().entries(self.iter()).finish()
     }
 }
+
+/// Heap allocated sequence without a bound on its length.
+#[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DynSeq {
+    values: Vec<usize>,
+}
+
+impl Seq for DynSeq {
+    type IterPerm = DynSeqPermutations;
+
+    fn new() -> Self {
+        Self {
+            values: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn get(&self, i: usize) -> usize {
+        self.values[i]
+    }
+
+    fn push(&mut self, val: usize) {
+        self.values.push(val);
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        self.values.pop()
+    }
+
+    fn reverse(&mut self) {
+        self.values.reverse();
+    }
+
+    fn permutations(mut self) -> DynSeqPermutations {
+        self.values.sort_unstable();
+
+        DynSeqPermutations {
+            seq: Some(self),
+        }
+    }
+}
+
+impl DynSeq {
+    pub fn slice(&self) -> &[usize] {
+        &self.values
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DynSeqPermutations {
+    seq: Option<DynSeq>,
+}
+
+impl DynSeqPermutations {
+    pub fn empty() -> Self {
+        Self {
+            seq: None
+        }
+    }
+}
+
+impl Iterator for DynSeqPermutations {
+    type Item = DynSeq;
+
+    fn next(&mut self) -> Option<DynSeq> {
+        let res = self.seq.clone();
+
+        if let Some(seq) = &mut self.seq {
+            let values = &mut seq.values;
+            let i = (1..values.len()).rev().find(|i| values[i-1] < values[*i]);
+
+            if let Some(i) = i {
+                let j = (i..values.len()).rev().find(|j| values[*j] > values[i-1]).unwrap();
+                values.swap(i-1, j);
+                values[i..].reverse();
+            } else {
+                self.seq = None;
+            }
+        }
+
+        res
+    }
+}
+
+impl std::fmt::Debug for DynSeq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
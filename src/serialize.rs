@@ -0,0 +1,241 @@
+//! Serde support, enabled with the `serde` feature.
+//!
+//! The serialized forms only depend on node labels and not on the internal
+//! representation:
+//!
+//! - graphs: `{"nodes": [0, 1, 2], "edges": [[0, 1], [1, 2]]}`
+//! - permutations: the image of each point, `[1, 0, 2, ...]`
+//! - sequences: the values, `[3, 1, 2]`
+//! - colorings: the color of each node or `null`, `[0, null, 1, ...]`
+//! - rotation systems: each node with its neighbours in the order of
+//!   `RotationSystem::after`, starting at the smallest neighbour,
+//!   `[[0, [1, 2]], [1, [2, 0]], [2, [0, 1]]]`
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use crate::bitset::Bitset;
+use crate::graph::{Graph, Coloring, BitsetGraph, BitsetRows, Graph16, SmallColoring};
+use crate::permutation::{Permutation, SmallPerm};
+use crate::seq::{Seq, SmallSeq};
+use crate::embedding::{self, RotationSystem, SmallRotationSystem};
+
+#[derive(Serialize, Deserialize)]
+struct GraphRepr {
+    nodes: Vec<usize>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl GraphRepr {
+    fn from_graph<G: Graph>(graph: &G) -> Self {
+        let mut edges: Vec<_> = graph.edges()
+            .map(|(u, v)| (u.min(v), u.max(v)))
+            .collect();
+        edges.sort_unstable();
+
+        Self {
+            nodes: graph.nodes().iter().collect(),
+            edges,
+        }
+    }
+
+    fn to_graph<G: Graph>(&self) -> Result<G, String> {
+        let mut graph = G::empty();
+        for u in &self.nodes {
+            if *u >= G::MAXN {
+                return Err(format!("node {} out of range, max {} nodes", u, G::MAXN))
+            }
+            graph.add_node(*u);
+        }
+        for (u, v) in &self.edges {
+            if u == v || *u >= G::MAXN || *v >= G::MAXN || !graph.has_node(*u) || !graph.has_node(*v) {
+                return Err(format!("invalid edge ({}, {})", u, v))
+            }
+            graph.add_edge(*u, *v);
+        }
+        Ok(graph)
+    }
+}
+
+impl<B: BitsetRows, const N: usize> Serialize for BitsetGraph<B, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphRepr::from_graph(self).serialize(serializer)
+    }
+}
+
+impl<'de, B: BitsetRows, const N: usize> Deserialize<'de> for BitsetGraph<B, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        GraphRepr::deserialize(deserializer)?.to_graph().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Graph16 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphRepr::from_graph(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph16 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        GraphRepr::deserialize(deserializer)?.to_graph().map_err(D::Error::custom)
+    }
+}
+
+impl<const N: usize> Serialize for SmallPerm<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(|(_, j)| j))
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for SmallPerm<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let perm = Vec::<usize>::deserialize(deserializer)?;
+        if perm.len() > N || perm.iter().any(|j| *j >= N) {
+            return Err(D::Error::custom(format!("permutation out of range, max {} points", N)))
+        }
+        Self::from_iter(perm.into_iter().enumerate())
+            .ok_or_else(|| D::Error::custom("not a permutation"))
+    }
+}
+
+impl<const N: usize> Serialize for SmallSeq<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for SmallSeq<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<usize>::deserialize(deserializer)?;
+        if values.len() > N || values.iter().any(|v| *v >= N) {
+            return Err(D::Error::custom(format!("sequence out of range, max {} values", N)))
+        }
+        let mut seq = Self::new();
+        for v in values {
+            seq.push(v);
+        }
+        Ok(seq)
+    }
+}
+
+impl<B: Bitset, const N: usize> Serialize for SmallColoring<B, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..N).map(|u| if self.defined(u) {
+            Some(self.get(u))
+        } else {
+            None
+        }))
+    }
+}
+
+impl<'de, B: Bitset, const N: usize> Deserialize<'de> for SmallColoring<B, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let colors = Vec::<Option<usize>>::deserialize(deserializer)?;
+        if colors.len() > N || colors.iter().flatten().any(|c| *c >= N) {
+            return Err(D::Error::custom(format!("coloring out of range, max {} nodes", N)))
+        }
+        let mut coloring = Self::new();
+        for (u, c) in colors.into_iter().enumerate() {
+            if let Some(c) = c {
+                coloring.set(u, c);
+            }
+        }
+        Ok(coloring)
+    }
+}
+
+impl<B, G, const N: usize> Serialize for SmallRotationSystem<B, G, N>
+    where B: Bitset + Copy, G: Graph<Set = B>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let graph = self.to_graph();
+        serializer.collect_seq(graph.nodes().iter().map(|u| {
+            let mut rotation = Vec::new();
+            if let Some(v0) = graph.siblings(u).smallest() {
+                let mut v = v0;
+                loop {
+                    rotation.push(v);
+                    v = self.after(u, v);
+                    if v == v0 {
+                        break
+                    }
+                }
+            }
+            (u, rotation)
+        }))
+    }
+}
+
+impl<'de, B, G, const N: usize> Deserialize<'de> for SmallRotationSystem<B, G, N>
+    where B: Bitset + Copy, G: Graph<Set = B, Embedding = Self>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rotations = Vec::<(usize, Vec<usize>)>::deserialize(deserializer)?;
+        embedding::from_rotations::<G>(&rotations)
+            .map_err(|i| D::Error::custom(format!("invalid rotation for node {}", rotations[i].0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph64, Coloring16};
+    use crate::permutation::Perm16;
+    use crate::{planar, toroidal, random};
+
+    #[test]
+    fn graph_round_trip() {
+        for _ in 0..20 {
+            let graph: Graph16 = random::graph(12);
+            let json = serde_json::to_string(&graph).unwrap();
+            assert_eq!(serde_json::from_str::<Graph16>(&json).unwrap(), graph);
+
+            let graph: Graph64 = random::graph(40);
+            let json = serde_json::to_string(&graph).unwrap();
+            assert_eq!(serde_json::from_str::<Graph64>(&json).unwrap(), graph);
+        }
+
+        let graph: Graph16 = serde_json::from_str(r#"{"nodes":[0,1,2],"edges":[[0,1],[1,2]]}"#).unwrap();
+        assert_eq!(serde_json::to_string(&graph).unwrap(), r#"{"nodes":[0,1,2],"edges":[[0,1],[1,2]]}"#);
+
+        assert!(serde_json::from_str::<Graph16>(r#"{"nodes":[0,1],"edges":[[0,2]]}"#).is_err());
+        assert!(serde_json::from_str::<Graph16>(r#"{"nodes":[16],"edges":[]}"#).is_err());
+    }
+
+    #[test]
+    fn perm_seq_coloring_round_trip() {
+        let perm: Perm16 = random::permutation(16);
+        let json = serde_json::to_string(&perm).unwrap();
+        assert_eq!(serde_json::from_str::<Perm16>(&json).unwrap(), perm);
+        assert_eq!(serde_json::from_str::<Perm16>("[1, 0]").unwrap().get(1), 0);
+        assert!(serde_json::from_str::<Perm16>("[1, 1]").is_err());
+        assert!(serde_json::from_str::<Perm16>("[16]").is_err());
+
+        let seq: SmallSeq<16> = serde_json::from_str("[3, 1, 2]").unwrap();
+        assert_eq!(seq.iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+        assert_eq!(serde_json::to_string(&seq).unwrap(), "[3,1,2]");
+
+        let mut coloring = Coloring16::new();
+        coloring.set(0, 1);
+        coloring.set(2, 0);
+        let json = serde_json::to_string(&coloring).unwrap();
+        assert!(json.starts_with("[1,null,0,null"));
+        assert_eq!(serde_json::from_str::<Coloring16>(&json).unwrap(), coloring);
+    }
+
+    #[test]
+    fn embedding_round_trip() {
+        let k4 = planar::fastdmp(&Graph16::complete(4)).unwrap();
+        let k7 = toroidal::find_embedding(&Graph16::complete(7)).unwrap();
+
+        for embedding in &[k4, k7] {
+            let json = serde_json::to_string(embedding).unwrap();
+            let parsed: <Graph16 as Graph>::Embedding = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.genus(), embedding.genus());
+            assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        }
+
+        assert!(serde_json::from_str::<<Graph16 as Graph>::Embedding>("[[0,[1]],[1,[]]]").is_err());
+        assert!(serde_json::from_str::<<Graph16 as Graph>::Embedding>("[[0,[2]],[1,[]],[2,[1]]]").is_err());
+        assert!(serde_json::from_str::<<Graph16 as Graph>::Embedding>("[[0,[1,1]],[1,[0]]]").is_err());
+    }
+}
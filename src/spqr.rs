@@ -0,0 +1,354 @@
+//! Triconnected components of a biconnected graph and the SPQR-tree joining
+//! them. The graph is split at separation pairs until only triangles, triple
+//! bonds and triconnected graphs remain, then bonds and polygons sharing a
+//! virtual edge are merged. This is quadratic in the number of nodes per
+//! split instead of the linear algorithm of Hopcroft and Tarjan, which is
+//! plenty for the graph sizes we work with.
+
+use crate::prelude::*;
+use crate::multigraph::Multigraph;
+
+/// An edge of a component, virtual edges come in pairs with the same id in
+/// the two components they join
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SpqrEdge {
+    pub u: usize,
+    pub v: usize,
+    pub virtual_id: Option<usize>,
+}
+
+impl SpqrEdge {
+    fn real(u: usize, v: usize) -> Self {
+        Self {
+            u,
+            v,
+            virtual_id: None,
+        }
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        self.virtual_id.is_some()
+    }
+
+    fn joins(&self, a: usize, b: usize) -> bool {
+        (self.u, self.v) == (a, b) || (self.u, self.v) == (b, a)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SpqrKind {
+    /// A cycle
+    S,
+    /// Two nodes joined by three or more parallel edges
+    P,
+    /// A triconnected simple graph
+    R,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpqrComponent {
+    pub kind: SpqrKind,
+    pub edges: Vec<SpqrEdge>,
+}
+
+impl SpqrComponent {
+    fn new(edges: Vec<SpqrEdge>) -> Self {
+        Self {
+            kind: classify(&edges),
+            edges,
+        }
+    }
+
+    pub fn nodes(&self) -> Vec<usize> {
+        let mut nodes: Vec<_> = self.edges.iter().flat_map(|e| vec![e.u, e.v]).collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes
+    }
+}
+
+fn classify(edges: &[SpqrEdge]) -> SpqrKind {
+    let (a, b) = (edges[0].u, edges[0].v);
+    if edges.iter().all(|e| e.joins(a, b)) {
+        return SpqrKind::P
+    }
+    let mut degree = std::collections::HashMap::new();
+    for e in edges {
+        *degree.entry(e.u).or_insert(0) += 1;
+        *degree.entry(e.v).or_insert(0) += 1;
+    }
+    if degree.values().all(|&d| d == 2) {
+        SpqrKind::S
+    } else {
+        SpqrKind::R
+    }
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    if parent[i] != i {
+        parent[i] = find(parent, parent[i]);
+    }
+    parent[i]
+}
+
+/// Separation classes of `edges` at `{a, b}`: two edges are in the same class
+/// when a path through neither `a` nor `b` joins them
+fn separation_classes(edges: &[SpqrEdge], a: usize, b: usize) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..edges.len()).collect();
+    let mut first_at = std::collections::HashMap::new();
+    for (i, e) in edges.iter().enumerate() {
+        for &x in &[e.u, e.v] {
+            if x == a || x == b {
+                continue
+            }
+            let j = *first_at.entry(x).or_insert(i);
+            let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+            parent[ri] = rj;
+        }
+    }
+
+    let mut classes: Vec<Vec<usize>> = Vec::new();
+    let mut class_of = std::collections::HashMap::new();
+    for i in 0..edges.len() {
+        let root = find(&mut parent, i);
+        let k = *class_of.entry(root).or_insert_with(|| {
+            classes.push(Vec::new());
+            classes.len() - 1
+        });
+        classes[k].push(i);
+    }
+    classes
+}
+
+/// A separation pair of the component and the edges to split off with it
+fn find_split(edges: &[SpqrEdge]) -> Option<(usize, usize, Vec<usize>)> {
+    if edges.len() <= 3 {
+        return None
+    }
+    let nodes = SpqrComponent { kind: SpqrKind::R, edges: edges.to_vec() }.nodes();
+
+    for (i, &a) in nodes.iter().enumerate() {
+        for &b in &nodes[i + 1..] {
+            let classes = separation_classes(edges, a, b);
+            if classes.len() < 2 {
+                continue
+            }
+            if let Some(class) = classes.iter().find(|c| c.len() >= 2 && edges.len() - c.len() >= 2) {
+                return Some((a, b, class.clone()))
+            }
+            // Only parallel edges between a and b, split off two of them
+            if classes.len() >= 4 && classes.iter().all(|c| c.len() == 1) {
+                return Some((a, b, vec![classes[0][0], classes[1][0]]))
+            }
+        }
+    }
+    None
+}
+
+pub struct SpqrTree<G: Graph> {
+    components: Vec<SpqrComponent>,
+    _marker: std::marker::PhantomData<G>,
+}
+
+impl<G: Graph> SpqrTree<G> {
+    /// The decomposition of a biconnected graph, `None` if `graph` is not
+    /// biconnected
+    pub fn new(graph: &G) -> Option<Self> {
+        if !crate::biconnected::is_biconnected(graph) {
+            return None
+        }
+
+        // Split into triangles, triple bonds and triconnected graphs
+        let mut next_id = 0;
+        let mut todo = vec![graph.edges().map(|(u, v)| SpqrEdge::real(u, v)).collect::<Vec<_>>()];
+        let mut split = Vec::new();
+        while let Some(edges) = todo.pop() {
+            match find_split(&edges) {
+                Some((a, b, class)) => {
+                    let virt = SpqrEdge { u: a, v: b, virtual_id: Some(next_id) };
+                    next_id += 1;
+                    let mut inside = vec![virt];
+                    let mut outside = vec![virt];
+                    for (i, e) in edges.into_iter().enumerate() {
+                        if class.contains(&i) {
+                            inside.push(e);
+                        } else {
+                            outside.push(e);
+                        }
+                    }
+                    todo.push(inside);
+                    todo.push(outside);
+                }
+                None => split.push(SpqrComponent::new(edges)),
+            }
+        }
+
+        // Merge bonds with bonds and polygons with polygons
+        let mut components: Vec<Option<SpqrComponent>> = split.into_iter().map(Some).collect();
+        'merge: loop {
+            for id in 0..next_id {
+                let holders: Vec<usize> = (0..components.len())
+                    .filter(|&i| components[i].as_ref().is_some_and(|c| {
+                        c.edges.iter().any(|e| e.virtual_id == Some(id))
+                    }))
+                    .collect();
+                if let [i, j] = holders[..] {
+                    let kind = components[i].as_ref().unwrap().kind;
+                    if kind != SpqrKind::R && kind == components[j].as_ref().unwrap().kind {
+                        let mut edges = components[i].take().unwrap().edges;
+                        edges.extend(components[j].take().unwrap().edges);
+                        edges.retain(|e| e.virtual_id != Some(id));
+                        components[i] = Some(SpqrComponent { kind, edges });
+                        continue 'merge
+                    }
+                }
+            }
+            break
+        }
+
+        Some(Self {
+            components: components.into_iter().flatten().collect(),
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    pub fn components(&self) -> &[SpqrComponent] {
+        &self.components
+    }
+
+    /// The edges of the tree as two component indices and the id of the
+    /// virtual edge they share
+    pub fn tree_edges(&self) -> Vec<(usize, usize, usize)> {
+        let mut holders = std::collections::BTreeMap::new();
+        for (i, component) in self.components.iter().enumerate() {
+            for id in component.edges.iter().filter_map(|e| e.virtual_id) {
+                holders.entry(id).or_insert_with(Vec::new).push(i);
+            }
+        }
+        holders.into_iter().map(|(id, held)| (held[0], held[1], id)).collect()
+    }
+
+    /// The skeleton of component `i`, virtual edges included
+    pub fn skeleton(&self, i: usize) -> Multigraph<G> {
+        let mut skeleton = Multigraph::empty();
+        for u in self.components[i].nodes() {
+            skeleton.add_node(u);
+        }
+        for e in &self.components[i].edges {
+            skeleton.add_edge(e.u, e.v);
+        }
+        skeleton
+    }
+
+    /// Rebuilds the graph from the real edges of the components
+    pub fn to_graph(&self) -> G {
+        let mut graph = G::empty();
+        for component in &self.components {
+            for e in component.edges.iter().filter(|e| !e.is_virtual()) {
+                graph.add_node(e.u);
+                graph.add_node(e.v);
+                graph.add_edge(e.u, e.v);
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32};
+    use crate::families::{complete_bipartite, cycle, path};
+
+    fn kinds<G: Graph>(tree: &SpqrTree<G>) -> Vec<SpqrKind> {
+        let mut kinds: Vec<_> = tree.components().iter().map(|c| c.kind).collect();
+        kinds.sort_by_key(|k| *k as usize);
+        kinds
+    }
+
+    /// Connected with at least four nodes and still connected after removing
+    /// any two
+    fn is_triconnected(graph: &Graph32) -> bool {
+        let nodes: Vec<_> = graph.nodes().iter().collect();
+        nodes.len() >= 4 && nodes.iter().all(|&a| nodes.iter().all(|&b| {
+            let mut without = *graph;
+            without.del_node(a);
+            without.del_node(b);
+            without.is_connected()
+        }))
+    }
+
+    fn check_tree(graph: &Graph32) {
+        let tree = SpqrTree::new(graph).unwrap();
+        assert_eq!(&tree.to_graph(), graph);
+
+        let edges = tree.tree_edges();
+        assert_eq!(edges.len() + 1, tree.components().len());
+        for &(i, j, _) in &edges {
+            let (a, b) = (tree.components()[i].kind, tree.components()[j].kind);
+            assert!(a == SpqrKind::R || a != b);
+        }
+
+        for (i, component) in tree.components().iter().enumerate() {
+            let skeleton = tree.skeleton(i);
+            match component.kind {
+                SpqrKind::S => assert!(component.edges.len() >= 3),
+                SpqrKind::P => assert!(component.edges.len() >= 3 && skeleton.simple().nodes().count() == 2),
+                SpqrKind::R => {
+                    assert_eq!(skeleton.edges_count(), skeleton.simple().edges_count());
+                    assert!(is_triconnected(skeleton.simple()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_graphs() {
+        let c5: Graph16 = cycle(5).unwrap();
+        assert_eq!(kinds(&SpqrTree::new(&c5).unwrap()), vec![SpqrKind::S]);
+
+        assert_eq!(kinds(&SpqrTree::new(&Graph16::complete(4)).unwrap()), vec![SpqrKind::R]);
+
+        // Three paths of length two between 0 and 1
+        let theta: Graph16 = complete_bipartite(2, 3).unwrap();
+        let tree = SpqrTree::new(&theta).unwrap();
+        assert_eq!(kinds(&tree), vec![SpqrKind::S, SpqrKind::S, SpqrKind::S, SpqrKind::P]);
+        assert_eq!(tree.to_graph(), theta);
+
+        // Two K4 sharing the edge (0, 1)
+        let mut glued = Graph16::complete(4);
+        for u in 4..6 {
+            glued.add_node(u);
+            glued.add_edge(u, 0);
+            glued.add_edge(u, 1);
+        }
+        glued.add_edge(4, 5);
+        let tree = SpqrTree::new(&glued).unwrap();
+        assert_eq!(kinds(&tree), vec![SpqrKind::P, SpqrKind::R, SpqrKind::R]);
+        assert_eq!(tree.tree_edges().len(), 2);
+
+        let path: Graph16 = path(3).unwrap();
+        assert!(SpqrTree::new(&path).is_none());
+    }
+
+    #[test]
+    fn random_graphs() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let mut checked = 0;
+        while checked < 30 {
+            let original: Graph32 = crate::random::graph(rng.gen_range(4..12));
+            let mut graph = original;
+            for (u, v) in original.edges() {
+                if rng.gen_bool(0.4) {
+                    graph.del_edge(u, v);
+                }
+            }
+            if crate::biconnected::is_biconnected(&graph) {
+                check_tree(&graph);
+                checked += 1;
+            }
+        }
+    }
+}
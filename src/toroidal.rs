@@ -156,18 +156,15 @@ impl<G: Graph> Embedder<G> {
             });
 
         if self.subgraph_embeddings.is_empty() {
-            let node_count = h.nodes().count();
-            let edge_count = h.edges_count();
-
-            self.find_embedding_with_embeddings(graph, &h, G::Embedding::enumerate(&h)
-                .filter(|embedding| (3 + edge_count - node_count - embedding.faces().count())/2 == 1))
+            let embeddings = torus_embeddings(&h);
+            self.find_embedding_with_embeddings(graph, &h, embeddings)
         } else {
-            let subgraph_embeddings = self.subgraph_embeddings.clone().into_iter();
+            let subgraph_embeddings = self.subgraph_embeddings.clone().into_iter().map(Box::new);
             self.find_embedding_with_embeddings(graph, &h, subgraph_embeddings)
         }
     }
 
-    fn find_embedding_with_embeddings<I: Iterator<Item = G::Embedding>>(self, graph: &G, h: &G, subgraph_embeddings: I) -> EmbeddingResult<G> {
+    fn find_embedding_with_embeddings<I: Iterator<Item = Box<G::Embedding>>>(self, graph: &G, h: &G, subgraph_embeddings: I) -> EmbeddingResult<G> {
         let mut filtered_embeddings = vec![false; self.subgraph_embeddings.len()];
         let mut i = 0;
 
@@ -226,18 +223,29 @@ impl<G: Graph> Embedder<G> {
         }
 
         EmbeddingResult {
-            embedding: res_embedding,
+            embedding: res_embedding.map(|embedding| *embedding),
             filtered_embeddings,
             remainding_bridges,
         }
     }
 }
 
+/// Genus one embeddings of `h`, the enumeration state of large rotation
+/// systems is kept on the heap
+fn torus_embeddings<G: Graph>(h: &G) -> impl Iterator<Item = Box<G::Embedding>> {
+    let node_count = h.nodes().count();
+    let edge_count = h.edges_count();
+
+    Box::new(G::Embedding::enumerate(h))
+        .filter(move |embedding| (3 + edge_count - node_count - embedding.faces().count())/2 == 1)
+        .map(Box::new)
+}
+
 pub fn find_embedding<G: Graph>(graph: &G) -> Option<G::Embedding> {
     Embedder::new().find_embedding(graph).embedding
 }
 
-fn search_embedding<G: Graph>(embedding: G::Embedding, bridges: &[G]) -> Result<G::Embedding, usize> {
+fn search_embedding<G: Graph>(embedding: Box<G::Embedding>, bridges: &[G]) -> Result<Box<G::Embedding>, usize> {
     if let Ok(res) = search_embedding_impl::<G, Bitset64, Map64<Bitset64>, Map64<G>, Map64<Face>>(embedding.clone(), &bridges) {
         res
     } else {
@@ -245,7 +253,7 @@ fn search_embedding<G: Graph>(embedding: G::Embedding, bridges: &[G]) -> Result<
     }
 }
 
-fn search_embedding_impl<G: Graph, S: Intset, SM, BM, FM>(embedding: G::Embedding, bridges: &[G]) -> Result<Result<G::Embedding, usize>, FullMapError>
+fn search_embedding_impl<G: Graph, S: Intset, SM, BM, FM>(embedding: Box<G::Embedding>, bridges: &[G]) -> Result<Result<Box<G::Embedding>, usize>, FullMapError>
     where SM: Slotmap<Output = S>,
           BM: Slotmap<Output = G>,
           FM: Slotmap<Output = Face>,
@@ -267,11 +275,11 @@ fn search_embedding_impl<G: Graph, S: Intset, SM, BM, FM>(embedding: G::Embeddin
 }
 
 struct TorusSearcher<G: Graph, S, SM, BM, FM> {
-    embedding: G::Embedding,
+    embedding: Box<G::Embedding>,
     admissible_faces: SM, //[Bitset16; 16],
     admissible_bridges: SM, // [HashSet<usize>; 16],
     one_admissible: S,
-    bridges: BM,
+    bridges: Box<BM>,
     faces: FM,
     h: G,
     h_nodes: G::Set,
@@ -287,14 +295,14 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
           for<'a> &'a FM: IntoIterator<Item = (usize, &'a Face)>,
           for<'a> &'a SM::Output: IntoIterator<Item = usize>,
 {
-    fn new(embedding: G::Embedding, bridges_list: &[G]) -> Result<Result<Self, usize>, FullMapError> {
+    fn new(embedding: Box<G::Embedding>, bridges_list: &[G]) -> Result<Result<Self, usize>, FullMapError> {
         let h = embedding.to_graph();
         let h_nodes = h.nodes();
         let mut faces = FM::new();
         for face in embedding.faces() {
             faces.push(face)?;
         }
-        let mut bridges = BM::new();
+        let mut bridges = Box::new(BM::new());
         for bridge in bridges_list {
             bridges.push(bridge.to_owned())?;
         }
@@ -309,7 +317,7 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
             admissible_bridges.insert(i, SM::Output::new());
         }
         let mut bridge_count = 0;
-        for (i, bridge) in &bridges {
+        for (i, bridge) in &*bridges {
             admissible_faces.insert(i, SM::Output::new());
             for (j, face) in &faces {
                 let attachments = h_nodes.intersection(&bridge.nodes());
@@ -396,6 +404,59 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
         (face, admissible_bridges)
     }
 
+    // `search` recurses once per bridge, the helpers below keep graphs out of
+    // its frame so that large graphs such as `Graph128` fit the default stack
+
+    #[inline(never)]
+    fn take_bridge(&mut self) -> (usize, Box<G>) {
+        if let Some(i) = self.one_admissible.smallest() {
+            self.one_admissible.clear(i);
+            (i, Box::new(self.bridges.take(i).unwrap()))
+        } else {
+            let (i, bridge) = self.bridges.pop().unwrap();
+            (i, Box::new(bridge))
+        }
+    }
+
+    #[inline(never)]
+    fn restore_bridge(&mut self, i: usize, bridge: &G) {
+        self.bridges.insert(i, bridge.clone());
+    }
+
+    /// Adds the bridges of `bridge` relative to `h`, returns their indices and
+    /// whether each of them has an admissible face
+    #[inline(never)]
+    fn add_bridges(&mut self, bridge: &G, faces: &SM::Output) -> Result<(SM::Output, bool), FullMapError> {
+        let new_bridges: Vec<G> = compute_bridges(bridge, &self.h, &self.h_nodes).collect();
+        let mut new_bridges_idx = SM::Output::new();
+        for new_bridge in new_bridges {
+            if let Some(i) = self.add_bridge(new_bridge, faces)? {
+                new_bridges_idx.set(i);
+            } else {
+                return Ok((new_bridges_idx, false))
+            }
+        }
+        Ok((new_bridges_idx, true))
+    }
+
+    #[inline(never)]
+    fn embed_path(&mut self, path: &G::Path, u: usize, v: usize) -> (Box<G>, [Face; 2]) {
+        let oldh = Box::new(self.h.clone());
+        let new_faces = self.embedding.embed_bisecting_path_after(path, u, v);
+        self.h.union(&G::from_path(path));
+        self.h_nodes = self.h.nodes();
+        (oldh, new_faces)
+    }
+
+    #[inline(never)]
+    fn unembed_path(&mut self, path: &G::Path, oldh: &G, old_h_nodes: G::Set) {
+        self.h.clone_from(oldh);
+        self.h_nodes = old_h_nodes;
+        for (u, v) in G::from_path(path).edges() {
+            self.embedding.remove_edge(u, v);
+        }
+    }
+
     fn search(&mut self) -> Result<bool, FullMapError> {
         //dbg!("search");
         //dbg!(&self.embedding);
@@ -404,12 +465,7 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
         if self.bridges.is_empty() {
             return Ok(true)
         }
-        let (bridge_i, bridge) = if let Some(i) = self.one_admissible.smallest() {
-            self.one_admissible.clear(i);
-            (i, self.bridges.take(i).unwrap())
-        } else {
-            self.bridges.pop().unwrap()
-        };
+        let (bridge_i, bridge) = self.take_bridge();
         let bridge_nodes = bridge.nodes();
 
         //let old_admissible_faces = self.admissible_faces[bridge_i];
@@ -439,21 +495,10 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
             self.h.add_node(end);
             self.h.add_edge(start, end);
 
-            let mut new_bridges_idx = SM::Output::new();
-
             let mut new_faces_idx = SM::Output::new();
             new_faces_idx.set(face_i);
 
-            let mut ok = true;
-
-            for new_bridge in compute_bridges(&bridge, &self.h.clone(), &self.h_nodes.clone()) {
-                if let Some(i) = self.add_bridge(new_bridge, &new_faces_idx)? {
-                    new_bridges_idx.set(i);
-                } else {
-                    ok = false;
-                    break;
-                }
-            }
+            let (new_bridges_idx, ok) = self.add_bridges(&bridge, &new_faces_idx)?;
 
             if !ok {
                 self.bridges_rem = std::cmp::min(self.bridges_rem, self.bridges.count()+1);
@@ -493,12 +538,8 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
                 for u in start_endpoints.iter() {
                     for v in end_endpoints.iter() {
                         //dbg!(u, v);
-                        let oldh = self.h.clone();
                         let old_h_nodes = self.h_nodes.clone();
-
-                        let new_faces = self.embedding.embed_bisecting_path_after(&path, u, v);
-                        self.h.union(&G::from_path(&path));
-                        self.h_nodes = self.h.nodes();
+                        let (oldh, new_faces) = self.embed_path(&path, u, v);
 
                         let mut new_faces_idx = SM::Output::new();
                         new_faces_idx.set(self.faces.push(new_faces[0])?);
@@ -525,14 +566,9 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
                         let mut new_bridges_idx = SM::Output::new();
 
                         if ok {
-                            for new_bridge in compute_bridges(&bridge, &self.h.clone(), &self.h_nodes.clone()) {
-                                if let Some(i) = self.add_bridge(new_bridge, &new_faces_idx)? {
-                                    new_bridges_idx.set(i);
-                                } else {
-                                    ok = false;
-                                    break;
-                                }
-                            }
+                            let (idx, added) = self.add_bridges(&bridge, &new_faces_idx)?;
+                            new_bridges_idx = idx;
+                            ok = added;
                         }
                         if !ok {
                             if self.h.is_supergraph(&bridge) {
@@ -544,12 +580,8 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
                             return Ok(true)
                         }
 
-                        self.h = oldh;
-                        self.h_nodes = old_h_nodes;
                         //dbg!(&path);
-                        for (u, v) in G::from_path(&path).edges() {
-                            self.embedding.remove_edge(u, v);
-                        }
+                        self.unembed_path(&path, &oldh, old_h_nodes);
 
                         for j in &new_faces_idx {
                             //dbg!("remove", j);
@@ -568,7 +600,7 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
                 }
             }
         }
-        self.bridges.insert(bridge_i, bridge);
+        self.restore_bridge(bridge_i, &bridge);
         self.admissible_faces.insert(bridge_i, old_admissible_faces);
         if self.admissible_faces[bridge_i].count() == 1 {
             self.one_admissible.set(bridge_i);
@@ -584,7 +616,7 @@ impl<G: Graph, S: Intset, SM, BM, FM> TorusSearcher<G, S, SM, BM, FM>
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graph::{subgraphs, Graph16};
+    use crate::graph::{subgraphs, Graph16, Graph128};
 
     fn test_is_toroidal(graph: &Graph16) {
         let embedding = find_embedding(graph);
@@ -633,4 +665,27 @@ mod tests {
         test_is_toroidal(&graph);
     }
 
+    #[test]
+    fn graph128_torus_grid() {
+        // 10x10 grid with wrap-around edges, too large for Graph64
+        let mut graph = Graph128::empty();
+        for u in 0..100 {
+            graph.add_node(u);
+        }
+        for x in 0..10 {
+            for y in 0..10 {
+                graph.add_edge(10*x + y, 10*((x + 1) % 10) + y);
+                graph.add_edge(10*x + y, 10*x + (y + 1) % 10);
+            }
+        }
+
+        let embedding = find_embedding(&graph).unwrap();
+        assert_eq!(embedding.genus(), 1);
+        assert_eq!(embedding.to_graph(), graph);
+
+        graph.add_edge(0, 55);
+        graph.add_edge(5, 50);
+        graph.add_edge(27, 72);
+        assert!(find_embedding(&graph).is_none());
+    }
 }
@@ -1,84 +1,645 @@
 use std::process::{Command, Stdio};
+use std::path::Path;
 use crate::prelude::*;
+use crate::drawing::TorusLayout;
 
+/// Styling of graphs in the DOT and TikZ outputs. Colors, labels and
+/// highlights are given per node, so every graph has its own options.
+#[derive(Clone, Debug)]
 pub struct GraphvizOptions {
     shape: String,
     colorscheme: String,
+    colorscheme_size: usize,
+    colors: Vec<Option<usize>>,
+    labels: Vec<Option<String>>,
+    highlighted: Vec<(usize, usize)>,
+    highlighted_nodes: Vec<usize>,
 }
 
-//pub fn graph2dot<G: Graph>(graphs: &[(G, Option<Coloring16>)]) -> String {
+/// The colors of the Graphviz `set312` scheme, used for TikZ
+const SET312: [(u8, u8, u8); 12] = [
+    (141, 211, 199), (255, 255, 179), (190, 186, 218), (251, 128, 114),
+    (128, 177, 211), (253, 180, 98), (179, 222, 105), (252, 205, 229),
+    (217, 217, 217), (188, 128, 189), (204, 235, 197), (255, 237, 111),
+];
+
+impl Default for GraphvizOptions {
+    fn default() -> Self {
+        Self {
+            shape: "circle".to_string(),
+            colorscheme: "set312".to_string(),
+            colorscheme_size: 12,
+            colors: Vec::new(),
+            labels: Vec::new(),
+            highlighted: Vec::new(),
+            highlighted_nodes: Vec::new(),
+        }
+    }
+}
+
+impl GraphvizOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Graphviz node shape. TikZ draws `box`, `rect` and `square` as
+    /// rectangles and every other shape as a circle.
+    pub fn shape(mut self, shape: &str) -> Self {
+        self.shape = shape.to_string();
+        self
+    }
+
+    /// Graphviz color scheme with `size` colors, at least one. TikZ always
+    /// uses the colors of `set312`.
+    pub fn colorscheme(mut self, colorscheme: &str, size: usize) -> Self {
+        self.colorscheme = colorscheme.to_string();
+        self.colorscheme_size = size.max(1);
+        self
+    }
+
+    /// Colors node `u` with color `c`, colors wrap around when the color
+    /// scheme has fewer colors.
+    pub fn color(mut self, u: usize, c: usize) -> Self {
+        if self.colors.len() <= u {
+            self.colors.resize(u + 1, None);
+        }
+        self.colors[u] = Some(c);
+        self
+    }
+
+    /// Colors all nodes defined in the coloring, e.g. the cells of a
+    /// refinement or the orbits of the automorphism group.
+    pub fn coloring<C: Coloring>(mut self, coloring: &C) -> Self {
+        for u in 0..C::Set::SIZE {
+            if coloring.defined(u) {
+                self = self.color(u, coloring.get(u));
+            }
+        }
+        self
+    }
+
+    /// Label of node `u` instead of its number
+    pub fn label(mut self, u: usize, label: &str) -> Self {
+        if self.labels.len() <= u {
+            self.labels.resize(u + 1, None);
+        }
+        self.labels[u] = Some(label.to_string());
+        self
+    }
+
+    /// Highlights the nodes and edges of `subgraph`, e.g. a Kuratowski
+    /// subgraph.
+    pub fn highlight<G: Graph>(mut self, subgraph: &G) -> Self {
+        self.highlighted_nodes.extend(subgraph.nodes().iter());
+        self.highlighted.extend(subgraph.edges().map(|(u, v)| (u.min(v), u.max(v))));
+        self
+    }
+
+    fn get_color(&self, u: usize) -> Option<usize> {
+        self.colors.get(u).copied().flatten()
+    }
+
+    fn get_label(&self, u: usize) -> String {
+        match self.labels.get(u) {
+            Some(Some(label)) => label.clone(),
+            _ => u.to_string(),
+        }
+    }
+
+    fn is_highlighted(&self, u: usize, v: usize) -> bool {
+        self.highlighted.contains(&(u.min(v), u.max(v)))
+    }
+
+    fn tikz_shape(&self) -> &'static str {
+        match self.shape.as_str() {
+            "box" | "rect" | "rectangle" | "square" => "rectangle",
+            _ => "circle",
+        }
+    }
+}
+
+/// Escapes a label for a quoted DOT string
+fn escape_dot(label: &str) -> String {
+    let mut res = String::new();
+    for c in label.chars() {
+        if c == '"' || c == '\\' {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+/// Escapes a label for TeX, quotes are braced so that they do not end the
+/// quoted node text of TikZ graphs
+fn escape_tex(label: &str) -> String {
+    let mut res = String::new();
+    for c in label.chars() {
+        match c {
+            '\\' => res.push_str("\\textbackslash{}"),
+            '~' => res.push_str("\\textasciitilde{}"),
+            '^' => res.push_str("\\textasciicircum{}"),
+            '"' => res.push_str("{\"}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                res.push('\\');
+                res.push(c);
+            }
+            _ => res.push(c),
+        }
+    }
+    res
+}
 
 pub fn graph2tikz<G: Graph>(graph: &G) -> String {
+    graph2tikz_with_options(graph, &GraphvizOptions::default())
+}
+
+pub fn graph2tikz_with_options<G: Graph>(graph: &G, opts: &GraphvizOptions) -> String {
     use std::fmt::Write;
     let mut tikz = String::new();
-    write!(tikz, "\\tikz \\graph[spring electrical layout]{{\n").unwrap();
+    writeln!(tikz, "\\tikz \\graph[spring electrical layout, nodes={{draw, {}}}]{{", opts.tikz_shape()).unwrap();
     for u in graph.nodes().iter() {
-        write!(tikz, "{};\n", u).unwrap();
+        let mut style = Vec::new();
+        if let Some(c) = opts.get_color(u) {
+            let (r, g, b) = SET312[c % SET312.len()];
+            style.push(format!("fill={{rgb,255:red,{};green,{};blue,{}}}", r, g, b));
+        }
+        if opts.highlighted_nodes.contains(&u) {
+            style.push("draw=red, thick".to_string());
+        }
+        writeln!(tikz, "{}/\"{}\"[{}];", u, escape_tex(&opts.get_label(u)), style.join(", ")).unwrap();
     }
     for (u, v) in graph.edges() {
-        write!(tikz, "{}--{};\n", u, v).unwrap();
+        if opts.is_highlighted(u, v) {
+            writeln!(tikz, "{} --[red, very thick] {};", u, v).unwrap();
+        } else {
+            writeln!(tikz, "{}--{};", u, v).unwrap();
+        }
     }
-    write!(tikz, "}};\n").unwrap();
+    writeln!(tikz, "}};").unwrap();
     tikz
 }
 
-pub fn graph2dot<G: Graph>(graphs: &[G]) -> String {
+/// Draws the graph in TikZ with the nodes at the positions of `layout`, one
+/// unit in the layout is `scale` cm.
+pub fn layout2tikz<G: Graph>(graph: &G, layout: &Layout, scale: f64) -> String {
     use std::fmt::Write;
-    let mut dot = String::new();
+    let mut tikz = String::new();
+    writeln!(tikz, "\\begin{{tikzpicture}}[scale={}, every node/.style={{draw, circle, inner sep=1pt, minimum size=12pt, fill=white}}]", scale).unwrap();
+    for (u, v) in graph.edges() {
+        let ((x1, y1), (x2, y2)) = (layout.get(u), layout.get(v));
+        writeln!(tikz, "\\draw ({}, {}) -- ({}, {});", x1, y1, x2, y2).unwrap();
+    }
+    for u in graph.nodes().iter() {
+        let (x, y) = layout.get(u);
+        writeln!(tikz, "\\node (n{}) at ({}, {}) {{{}}};", u, x, y, u).unwrap();
+    }
+    writeln!(tikz, "\\end{{tikzpicture}}").unwrap();
+    tikz
+}
 
-    let opts = GraphvizOptions {
-        shape: "circle".to_string(),
-        colorscheme: "set312".to_string(),
-    };
+pub fn graph2dot<G: Graph + Clone>(graphs: &[G]) -> String {
+    let graphs: Vec<_> = graphs.iter()
+        .map(|graph| (graph.clone(), GraphvizOptions::default()))
+        .collect();
+    graph2dot_with_options(&graphs)
+}
 
+/// Writes the graphs as clusters of a single DOT graph, each labeled with its
+/// index.
+pub fn graph2dot_with_options<G: Graph>(graphs: &[(G, GraphvizOptions)]) -> String {
+    use std::fmt::Write;
+    let mut dot = String::new();
 
-    write!(dot, "graph {{\n").unwrap();
-    write!(dot, "    node[shape = {} width=0.2 style=filled colorscheme={}]\n", 
-        opts.shape, opts.colorscheme).unwrap();
-    for (gi, graph) in graphs.iter().enumerate() {
-        write!(dot, "subgraph cluster{} {{\n", gi).unwrap();
-        write!(dot, "    label=\"{}\";\n", gi).unwrap();
+    writeln!(dot, "graph {{").unwrap();
+    writeln!(dot, "    node[width=0.2 style=filled fillcolor=white]").unwrap();
+    for (gi, (graph, opts)) in graphs.iter().enumerate() {
+        writeln!(dot, "subgraph cluster{} {{", gi).unwrap();
+        writeln!(dot, "    label=\"{}\";", gi).unwrap();
         for u in graph.nodes().iter() {
-            /*
-            if let Some(c) = coloring.as_ref()
-                .map(|coloring| coloring.get(u))
-            {
-                write!(dot, "    g{}n{}[label=\"{}\", fillcolor={}];\n", gi, u, u, c+1)
-                    .unwrap();
-            } else {
-            */
-                write!(dot, "    g{}n{}[label=\"{}\"];\n", gi, u, u)
-                    .unwrap();
-            //}
-            //write!(dot, "    {};\n", u);
+            write!(dot, "    g{}n{}[label=\"{}\" shape={} colorscheme={}",
+                gi, u, escape_dot(&opts.get_label(u)), opts.shape, opts.colorscheme).unwrap();
+            if let Some(c) = opts.get_color(u) {
+                write!(dot, " fillcolor={}", c % opts.colorscheme_size + 1).unwrap();
+            }
+            if opts.highlighted_nodes.contains(&u) {
+                write!(dot, " color=red penwidth=2").unwrap();
+            }
+            writeln!(dot, "];").unwrap();
         }
         for (u, v) in graph.edges() {
-            write!(dot, "    g{}n{} -- g{}n{};\n", gi, u, gi, v)
-                .unwrap();
+            write!(dot, "    g{}n{} -- g{}n{}", gi, u, gi, v).unwrap();
+            if opts.is_highlighted(u, v) {
+                write!(dot, "[color=red penwidth=2.5]").unwrap();
+            }
+            writeln!(dot, ";").unwrap();
         }
-        write!(dot, "}}\n").unwrap();
+        writeln!(dot, "}}").unwrap();
     }
-    write!(dot, "}}\n").unwrap();
+    writeln!(dot, "}}").unwrap();
     dot
 }
 
-//pub fn render_dot(file: &str, graphs: &[(Graph16, Option<Coloring16>)]) {
-pub fn render_dot<G: Graph>(file: &str, graphs: &[G]) {
+/// Renders the graphs to `file` with Graphviz `fdp`, the output format is
+/// always pdf. Fails if `fdp` is not installed or exits with an error.
+pub fn render_dot<G: Graph + Clone>(file: &str, graphs: &[G]) -> std::io::Result<()> {
+    let graphs: Vec<_> = graphs.iter()
+        .map(|graph| (graph.clone(), GraphvizOptions::default()))
+        .collect();
+    render_dot_with_options(file, &graphs)
+}
+
+/// Like `render_dot` with separate options for each graph
+pub fn render_dot_with_options<G: Graph>(file: &str, graphs: &[(G, GraphvizOptions)]) -> std::io::Result<()> {
     use std::io::Write;
-    let dot = graph2dot(graphs);
+    let dot = graph2dot_with_options(graphs);
 
     let mut proc = Command::new("fdp")
         .arg("-Tpdf")
         .arg("-o").arg(file)
         .stdin(Stdio::piped())
-        .spawn().unwrap();
-
-    //dbg!(&dot);
+        .spawn()?;
 
     {
         let mut stdin = proc.stdin.take().unwrap();
-        write!(stdin, "{}", dot).unwrap();
-        stdin.flush().unwrap();
+        write!(stdin, "{}", dot)?;
+        stdin.flush()?;
+    }
+
+    let status = proc.wait()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("fdp failed with {}", status)))
+    }
+    Ok(())
+}
+
+/// Node positions of a drawing, indexed by node. The y axis points up as in
+/// TikZ, `normalize` scales the positions to the unit square.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub positions: Vec<Option<(f64, f64)>>,
+}
+
+impl Layout {
+    pub fn get(&self, u: usize) -> (f64, f64) {
+        self.positions[u].unwrap()
+    }
+
+    /// The nodes with a position
+    pub fn nodes<'a>(&'a self) -> impl 'a + Iterator<Item = (usize, (f64, f64))> {
+        self.positions.iter().enumerate().filter_map(|(u, p)| p.map(|p| (u, p)))
+    }
+
+    /// Scales and translates the positions to fill the unit square, keeping
+    /// the aspect ratio.
+    pub fn normalize(&mut self) {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in self.positions.iter().flatten() {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+
+        let size = (max_x - min_x).max(max_y - min_y);
+        let (off_x, off_y) = ((size - (max_x - min_x)) / 2.0, (size - (max_y - min_y)) / 2.0);
+        for (x, y) in self.positions.iter_mut().flatten() {
+            if size > 0.0 {
+                *x = (*x - min_x + off_x) / size;
+                *y = (*y - min_y + off_y) / size;
+            } else {
+                *x = 0.5;
+                *y = 0.5;
+            }
+        }
+    }
+}
+
+/// Force-directed layout after Fruchterman and Reingold. The nodes start on a
+/// circle so the layout is deterministic.
+pub fn spring_layout<G: Graph>(graph: &G) -> Layout {
+    const ITERATIONS: usize = 300;
+
+    let nodes: Vec<usize> = graph.nodes().iter().collect();
+    let n = nodes.len();
+    let mut pos: Vec<(f64, f64)> = (0..n).map(|i| {
+        let a = 2.0 * std::f64::consts::PI * (i as f64) / (n as f64);
+        // Slightly uneven radii so symmetric starting positions can separate
+        let r = 0.4 + 0.05 * ((i % 3) as f64);
+        (0.5 + r * a.cos(), 0.5 + r * a.sin())
+    }).collect();
+
+    let k = (1.0 / n.max(1) as f64).sqrt();
+    let mut temperature = 0.1;
+    for _ in 0..ITERATIONS {
+        let mut disp = vec![(0.0, 0.0); n];
+
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue
+                }
+                let (dx, dy) = (pos[i].0 - pos[j].0, pos[i].1 - pos[j].1);
+                let d = (dx*dx + dy*dy).sqrt().max(1e-6);
+                let mut f = k * k / d;
+                if graph.has_edge(nodes[i], nodes[j]) {
+                    f -= d * d / k;
+                }
+                disp[i].0 += dx / d * f;
+                disp[i].1 += dy / d * f;
+            }
+        }
+
+        for i in 0..n {
+            let (dx, dy) = disp[i];
+            let d = (dx*dx + dy*dy).sqrt().max(1e-6);
+            let step = d.min(temperature);
+            pos[i].0 = (pos[i].0 + dx / d * step).clamp(0.0, 1.0);
+            pos[i].1 = (pos[i].1 + dy / d * step).clamp(0.0, 1.0);
+        }
+
+        temperature *= 0.98;
+    }
+
+    let mut positions = vec![None; G::MAXN];
+    for (u, p) in nodes.iter().zip(pos) {
+        positions[*u] = Some(p);
+    }
+    let mut layout = Layout { positions };
+    layout.normalize();
+    layout
+}
+
+const SVG_CELL: f64 = 240.0;
+const SVG_MARGIN: f64 = 30.0;
+const SVG_RADIUS: f64 = 9.0;
+
+fn write_svg_graph<G: Graph>(svg: &mut String, graph: &G, layout: &Layout, x0: f64, y0: f64) {
+    use std::fmt::Write;
+    let size = SVG_CELL - 2.0 * SVG_MARGIN;
+    let coord = |u| {
+        let (x, y) = layout.get(u);
+        (x0 + SVG_MARGIN + x * size, y0 + SVG_MARGIN + (1.0 - y) * size)
+    };
+
+    for (u, v) in graph.edges() {
+        let ((x1, y1), (x2, y2)) = (coord(u), coord(v));
+        writeln!(svg, "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\"/>",
+            x1, y1, x2, y2).unwrap();
+    }
+    for u in graph.nodes().iter() {
+        let (x, y) = coord(u);
+        writeln!(svg, "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"white\" stroke=\"black\"/>",
+            x, y, SVG_RADIUS).unwrap();
+        writeln!(svg, "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+            x, y, u).unwrap();
+    }
+}
+
+/// Draws the graphs with fixed layouts in a grid, each labeled with its index.
+/// The layouts are normalized to fit the grid cells.
+pub fn layouts2svg<G: Graph>(graphs: &[(G, Layout)]) -> String {
+    use std::fmt::Write;
+    let cols = ((graphs.len() as f64).sqrt().ceil() as usize).max(1);
+    let rows = graphs.len().div_ceil(cols);
+
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        cols as f64 * SVG_CELL, rows as f64 * SVG_CELL).unwrap();
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+    for (gi, (graph, layout)) in graphs.iter().enumerate() {
+        let x0 = (gi % cols) as f64 * SVG_CELL;
+        let y0 = (gi / cols) as f64 * SVG_CELL;
+        let mut layout = layout.clone();
+        layout.normalize();
+        writeln!(svg, "<g id=\"g{}\">", gi).unwrap();
+        writeln!(svg, "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"gray\"/>",
+            x0 + 2.0, y0 + 2.0, SVG_CELL - 4.0, SVG_CELL - 4.0).unwrap();
+        writeln!(svg, "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"12\">{}</text>",
+            x0 + 8.0, y0 + 16.0, gi).unwrap();
+        write_svg_graph(&mut svg, graph, &layout, x0, y0);
+        writeln!(svg, "</g>").unwrap();
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// Draws the graphs with `spring_layout` in a grid, like the clusters of
+/// `graph2dot`.
+pub fn graph2svg<G: Graph + Clone>(graphs: &[G]) -> String {
+    let graphs: Vec<_> = graphs.iter()
+        .map(|graph| (graph.clone(), spring_layout(graph)))
+        .collect();
+    layouts2svg(&graphs)
+}
+
+/// Renders the graphs to an svg file without any external tools.
+pub fn render_svg<G: Graph + Clone, P: AsRef<Path>>(file: P, graphs: &[G]) -> std::io::Result<()> {
+    std::fs::write(file, graph2svg(graphs))
+}
+
+/// The translations of the unit square an edge with offset `(dx, dy)` passes
+/// through.
+fn torus_copies(offset: (i64, i64)) -> impl Iterator<Item = (f64, f64)> {
+    let (dx, dy) = offset;
+    (dx.min(0)..=dx.max(0)).flat_map(move |i| {
+        (dy.min(0)..=dy.max(0)).map(move |j| (i as f64, j as f64))
+    })
+}
+
+/// Draws a torus layout in svg as the unit square with identified sides,
+/// marked by arrows.
+pub fn torus2svg(torus: &TorusLayout) -> String {
+    use std::fmt::Write;
+    let size = SVG_CELL * 2.0;
+    let coord = |(x, y): (f64, f64)| (SVG_MARGIN + x * size, SVG_MARGIN + (1.0 - y) * size);
+    let total = size + 2.0 * SVG_MARGIN;
+
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">", total, total).unwrap();
+    writeln!(svg, "<defs><clipPath id=\"torus\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath></defs>",
+        SVG_MARGIN, SVG_MARGIN, size, size).unwrap();
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+    writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"gray\"/>",
+        SVG_MARGIN, SVG_MARGIN, size, size).unwrap();
+
+    // Single arrows on the horizontal sides and double arrows on the vertical
+    // sides show which sides are identified
+    let arrow = |svg: &mut String, (x, y): (f64, f64), (dx, dy): (f64, f64)| {
+        let (px, py) = (-dy, dx);
+        writeln!(svg, "<polyline points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"none\" stroke=\"gray\"/>",
+            x - 6.0 * dx + 5.0 * px, y - 6.0 * dy + 5.0 * py, x, y,
+            x - 6.0 * dx - 5.0 * px, y - 6.0 * dy - 5.0 * py).unwrap();
+    };
+    for y in &[0.0, 1.0] {
+        arrow(&mut svg, coord((0.5, *y)), (1.0, 0.0));
+    }
+    for x in &[0.0, 1.0] {
+        arrow(&mut svg, coord((*x, 0.5)), (0.0, -1.0));
+        arrow(&mut svg, coord((*x, 0.53)), (0.0, -1.0));
+    }
+
+    writeln!(svg, "<g clip-path=\"url(#torus)\">").unwrap();
+    for (u, v, offset) in &torus.edges {
+        let (xu, yu) = torus.layout.get(*u);
+        let (xv, yv) = torus.layout.get(*v);
+        for (i, j) in torus_copies(*offset) {
+            let (x1, y1) = coord((xu - i, yu - j));
+            let (x2, y2) = coord((xv + offset.0 as f64 - i, yv + offset.1 as f64 - j));
+            writeln!(svg, "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\"/>",
+                x1, y1, x2, y2).unwrap();
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    for (u, p) in torus.layout.nodes() {
+        let (x, y) = coord(p);
+        writeln!(svg, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"white\" stroke=\"black\"/>",
+            x, y, SVG_RADIUS).unwrap();
+        writeln!(svg, "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+            x, y, u).unwrap();
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// Draws a torus layout in TikZ as a square of side `scale` cm with identified
+/// sides, marked by arrows.
+pub fn torus2tikz(torus: &TorusLayout, scale: f64) -> String {
+    use std::fmt::Write;
+    let mut tikz = String::new();
+    writeln!(tikz, "\\begin{{tikzpicture}}[scale={}, every node/.style={{draw, circle, inner sep=1pt, minimum size=12pt, fill=white}}]", scale).unwrap();
+    writeln!(tikz, "\\draw[gray] (0, 0) rectangle (1, 1);").unwrap();
+    writeln!(tikz, "\\draw[gray, ->] (0.45, 0) -- (0.55, 0);").unwrap();
+    writeln!(tikz, "\\draw[gray, ->] (0.45, 1) -- (0.55, 1);").unwrap();
+    writeln!(tikz, "\\draw[gray, ->>] (0, 0.45) -- (0, 0.55);").unwrap();
+    writeln!(tikz, "\\draw[gray, ->>] (1, 0.45) -- (1, 0.55);").unwrap();
+    writeln!(tikz, "\\begin{{scope}}").unwrap();
+    writeln!(tikz, "\\clip (0, 0) rectangle (1, 1);").unwrap();
+    for (u, v, offset) in &torus.edges {
+        let (xu, yu) = torus.layout.get(*u);
+        let (xv, yv) = torus.layout.get(*v);
+        for (i, j) in torus_copies(*offset) {
+            writeln!(tikz, "\\draw ({:.4}, {:.4}) -- ({:.4}, {:.4});",
+                xu - i, yu - j, xv + offset.0 as f64 - i, yv + offset.1 as f64 - j).unwrap();
+        }
+    }
+    writeln!(tikz, "\\end{{scope}}").unwrap();
+    for (u, (x, y)) in torus.layout.nodes() {
+        writeln!(tikz, "\\node (n{}) at ({:.4}, {:.4}) {{{}}};", u, x, y, u).unwrap();
+    }
+    writeln!(tikz, "\\end{{tikzpicture}}").unwrap();
+    tikz
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph16, Graph32};
+
+    #[test]
+    fn spring_layout_separates_nodes() {
+        let mut graph = Graph32::complete(6);
+        graph.add_node(20);
+        let layout = spring_layout(&graph);
+
+        let nodes: Vec<_> = graph.nodes().iter().collect();
+        for u in &nodes {
+            let (x, y) = layout.get(*u);
+            assert!((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y));
+            for v in &nodes {
+                let (xv, yv) = layout.get(*v);
+                assert!(u == v || (x - xv).abs() + (y - yv).abs() > 0.05);
+            }
+        }
+        assert_eq!(layout.positions[7], None);
+    }
+
+    #[test]
+    fn svg_grid() {
+        let graphs = [Graph16::complete(4), Graph16::complete(3), Graph16::empty()];
+        let svg = graph2svg(&graphs);
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("width=\"480\" height=\"480\""));
+        assert_eq!(svg.matches("<circle").count(), 7);
+        assert_eq!(svg.matches("<line").count(), 9);
+        assert_eq!(svg.matches("<g id").count(), 3);
+    }
+
+    #[test]
+    fn tikz_fixed_layout() {
+        let graph = Graph16::complete(2);
+        let mut positions = vec![None; 16];
+        positions[0] = Some((0.0, 0.0));
+        positions[1] = Some((2.0, 1.0));
+        let tikz = layout2tikz(&graph, &Layout { positions }, 0.5);
+
+        assert!(tikz.starts_with("\\begin{tikzpicture}[scale=0.5"));
+        assert!(tikz.contains("\\draw (2, 1) -- (0, 0);") || tikz.contains("\\draw (0, 0) -- (2, 1);"));
+        assert!(tikz.contains("\\node (n1) at (2, 1) {1};"));
+    }
+
+    #[test]
+    fn torus_output() {
+        let embedding = crate::toroidal::find_embedding(&Graph16::complete(7)).unwrap();
+        let torus = crate::drawing::torus_layout(&embedding).unwrap();
+
+        let svg = torus2svg(&torus);
+        assert_eq!(svg.matches("<circle").count(), 7);
+        assert!(svg.matches("<line").count() >= 21);
+        assert!(svg.contains("clip-path=\"url(#torus)\""));
+
+        let tikz = torus2tikz(&torus, 4.0);
+        assert_eq!(tikz.matches("\\node").count(), 7);
+        assert!(tikz.matches("\\draw (").count() >= 21);
+    }
+
+    #[test]
+    fn options() {
+        let graph = Graph16::complete(5);
+        let mut kuratowski = Graph16::complete(4);
+        kuratowski.del_edge(0, 1);
+        let mut coloring = <Graph16 as Graph>::Coloring::new();
+        coloring.set(0, 0);
+        coloring.set(1, 13);
+
+        let opts = GraphvizOptions::new()
+            .shape("box")
+            .coloring(&coloring)
+            .label(4, "x")
+            .highlight(&kuratowski);
+
+        let dot = graph2dot_with_options(&[(graph, opts.clone())]);
+        assert!(dot.contains("g0n0[label=\"0\" shape=box colorscheme=set312 fillcolor=1 color=red penwidth=2];"));
+        assert!(dot.contains("g0n1[label=\"1\" shape=box colorscheme=set312 fillcolor=2 color=red penwidth=2];"));
+        assert!(dot.contains("g0n4[label=\"x\" shape=box colorscheme=set312];"));
+        assert_eq!(dot.matches("[color=red penwidth=2.5]").count(), 5);
+
+        let tikz = graph2tikz_with_options(&graph, &opts);
+        assert!(tikz.contains("nodes={draw, rectangle}"));
+        assert!(tikz.contains("0/\"0\"[fill={rgb,255:red,141;green,211;blue,199}, draw=red, thick];"));
+        assert!(tikz.contains("4/\"x\"[];"));
+        assert_eq!(tikz.matches("--[red, very thick]").count(), 5);
+    }
+
+    #[test]
+    fn escaped_labels() {
+        let graph: Graph16 = crate::families::path(2).unwrap();
+        let opts = GraphvizOptions::new()
+            .colorscheme("set312", 0)
+            .color(0, 3)
+            .label(0, "a\"b")
+            .label(1, "c\\d_1");
+
+        let dot = graph2dot_with_options(&[(graph, opts.clone())]);
+        assert!(dot.contains("g0n0[label=\"a\\\"b\" shape=circle colorscheme=set312 fillcolor=1];"));
+        assert!(dot.contains("g0n1[label=\"c\\\\d_1\""));
+
+        let tikz = graph2tikz_with_options(&graph, &opts);
+        assert!(tikz.contains("nodes={draw, circle}"));
+        assert!(tikz.contains("0/\"a{\"}b\"["));
+        assert!(tikz.contains("1/\"c\\textbackslash{}d\\_1\"[];"));
     }
-    proc.wait().unwrap();
 }